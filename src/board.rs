@@ -1,17 +1,36 @@
-use std::{
-    fmt::Display,
-    ops::{Index, IndexMut},
-};
+use std::fmt::Display;
 
 use piece::Piece;
 
 use crate::pgn::mov::Move;
 
+mod attacks;
+mod bitboard;
+use bitboard::Bitboard;
+mod castling;
 mod display;
+mod fen;
+pub use fen::FenError;
 pub(crate) mod file;
 mod index;
 pub use index::Coord;
+mod material;
+pub use material::{Imbalance, Material, Phase, Side};
+mod movegen;
+pub use movegen::LegalMove;
+mod perft;
 pub mod piece;
+mod replay;
+pub use replay::{Draw, Replay};
+mod san;
+mod see;
+pub use see::MaterialLoss;
+mod transform;
+mod uci;
+pub use uci::UciError;
+mod undo;
+pub use undo::Undo;
+mod zobrist;
 
 #[cfg(test)]
 mod tests;
@@ -26,16 +45,18 @@ pub enum PieceType {
     Pawn,
 }
 
-impl From<char> for PieceType {
-    fn from(c: char) -> Self {
+impl TryFrom<char> for PieceType {
+    type Error = ();
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            'K' | 'O' => Self::King,
-            'Q' => Self::Queen,
-            'R' => Self::Rook,
-            'B' => Self::Bishop,
-            'N' => Self::Knight,
-            'P' => Self::Pawn,
-            _ => todo!("what is this? {c}"),
+            'K' | 'O' => Ok(Self::King),
+            'Q' => Ok(Self::Queen),
+            'R' => Ok(Self::Rook),
+            'B' => Ok(Self::Bishop),
+            'N' => Ok(Self::Knight),
+            'P' => Ok(Self::Pawn),
+            _ => Err(()),
         }
     }
 }
@@ -69,6 +90,30 @@ impl PieceType {
     pub fn is_pawn(&self) -> bool {
         matches!(self, Self::Pawn)
     }
+
+    /// the usual value of the piece in pawns. the king can never be traded,
+    /// so it's worth more than all of the other pieces put together
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::King => 1000,
+            PieceType::Queen => 9,
+            PieceType::Rook => 5,
+            PieceType::Bishop | PieceType::Knight => 3,
+            PieceType::Pawn => 1,
+        }
+    }
+
+    /// the lowercase English name of the piece, like `knight`
+    pub fn name(&self) -> &'static str {
+        match self {
+            PieceType::King => "king",
+            PieceType::Queen => "queen",
+            PieceType::Rook => "rook",
+            PieceType::Bishop => "bishop",
+            PieceType::Knight => "knight",
+            PieceType::Pawn => "pawn",
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -127,17 +172,71 @@ macro_rules! white {
 
 pub(crate) type Square = (usize, usize);
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Board {
-    board: [[Piece; 8]; 8],
+    /// the squares occupied by each type of piece, indexed by `typ as usize`
+    pieces: [Bitboard; 6],
+
+    /// the squares occupied by each color, indexed by [Color::index]
+    colors: [Bitboard; 2],
+
+    /// the piece on each square, indexed by [bitboard::index], for looking up
+    /// single squares and [std::ops::Index]
+    mailbox: [Piece; 64],
+
     half_move_clock: usize,
+
+    /// the side to move
+    to_move: Color,
+
+    /// the number of the current full move, starting at 1 and incremented
+    /// after each of Black's moves
+    full_move: usize,
+
     en_passant_target: Option<Square>,
-    white_can_castle_kingside: bool,
-    white_can_castle_queenside: bool,
-    black_can_castle_kingside: bool,
-    black_can_castle_queenside: bool,
+
+    /// the file of the rook each castling right belongs to, for White
+    /// kingside, White queenside, Black kingside, and Black queenside. these
+    /// are always the h- and a-files outside of Chess960
+    castling: [Option<usize>; 4],
+
+    /// whether castling moves are written as the king capturing its own rook
+    /// in UCI notation, as Chess960 engines expect
+    chess960: bool,
+
+    /// the Zobrist key of the position, kept up to date by [Board::play]
+    hash: u64,
+}
+
+/// a SAN move that doesn't describe exactly one legal move in the position
+/// it was played in, either because there is no such move or because it's
+/// ambiguous
+#[derive(Clone, Debug, PartialEq)]
+pub struct IllegalMove {
+    /// the half move the move was played on, counting White's first move as
+    /// 1, so that it lines up with the game's move numbers
+    pub ply: usize,
+
+    pub san: String,
+
+    /// the FEN of the position the move was played in
+    pub fen: String,
+}
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = self.ply.div_ceil(2);
+        let dots = if (self.ply & 1) == 1 { "." } else { "..." };
+        write!(
+            f,
+            "illegal move {number}{dots} {} in position {}",
+            self.san, self.fen
+        )
+    }
 }
 
+impl std::error::Error for IllegalMove {}
+
 impl Default for Board {
     fn default() -> Self {
         Self::new()
@@ -158,148 +257,211 @@ impl Board {
             white![Pawn, Pawn, Pawn, Pawn, Pawn, Pawn, Pawn, Pawn],
             white![Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook],
         ];
+        let mut ret = Self {
+            castling: [Some(7), Some(0), Some(7), Some(0)],
+            ..Self::empty()
+        };
+        ret.set_placement(board);
+        ret.hash = ret.compute_hash();
+        ret
+    }
+
+    /// a board with no pieces, White to move, and no castling rights
+    fn empty() -> Self {
         Self {
-            board,
+            pieces: [0; 6],
+            colors: [0; 2],
+            mailbox: [Piece::None; 64],
             half_move_clock: 0,
+            to_move: Color::White,
+            full_move: 1,
             en_passant_target: None,
-            white_can_castle_kingside: true,
-            white_can_castle_queenside: true,
-            black_can_castle_kingside: true,
-            black_can_castle_queenside: true,
+            castling: [None; 4],
+            chess960: false,
+            hash: 0,
         }
     }
 
-    /// move the piece in the square `from` to `to`, leaving `self[to]` empty
-    fn swap<T>(&mut self, from: T, to: T)
-    where
-        Self: Index<T> + IndexMut<T>,
-        <Self as Index<T>>::Output: Default,
-    {
-        self[to] = std::mem::take(&mut self[from]);
+    /// place the pieces in `rows`, starting from the eighth rank like a FEN
+    fn set_placement(&mut self, rows: [[Piece; 8]; 8]) {
+        for (i, row) in rows.into_iter().enumerate() {
+            for (file, piece) in row.into_iter().enumerate() {
+                self.put((7 - i, file), piece);
+            }
+        }
     }
 
-    pub(crate) fn make_move(&mut self, m: &Move, c: Color) {
-        use Coord::*;
-        match m {
-            Move::KingCastle => {
-                self.half_move_clock += 1;
-                match c {
-                    Color::Black => {
-                        self.swap(E8, G8); // King
-                        self.swap(H8, F8); // Rook
-                        self.black_can_castle_kingside = false;
-                        self.black_can_castle_queenside = false;
-                    }
-                    Color::White => {
-                        self.swap(E1, G1); // King
-                        self.swap(H1, F1); // Rook
-                        self.white_can_castle_kingside = false;
-                        self.white_can_castle_queenside = false;
-                    }
-                }
-            }
-            Move::QueenCastle => {
-                self.half_move_clock += 1;
-                match c {
-                    Color::Black => {
-                        self.swap(E8, C8); // King
-                        self.swap(A8, D8); // Rook
-                        self.black_can_castle_kingside = false;
-                        self.black_can_castle_queenside = false;
+    /// the squares occupied by either color
+    fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// the squares occupied by pieces of type `typ` and color `color`
+    fn bitboard(&self, typ: PieceType, color: Color) -> Bitboard {
+        self.pieces[typ as usize] & self.colors[color.index()]
+    }
+
+    /// set the square `sq` to `piece`, keeping the bitboards and hash up to
+    /// date
+    pub(crate) fn put(&mut self, sq: Square, piece: Piece) {
+        let i = bitboard::index(sq);
+        let bit = bitboard::bit(sq);
+        if let Piece::Some { typ, color } = self.mailbox[i] {
+            self.pieces[typ as usize] &= !bit;
+            self.colors[color.index()] &= !bit;
+        }
+        if let Piece::Some { typ, color } = piece {
+            self.pieces[typ as usize] |= bit;
+            self.colors[color.index()] |= bit;
+        }
+        self.hash ^= zobrist::piece_key(sq, self.mailbox[i])
+            ^ zobrist::piece_key(sq, piece);
+        self.mailbox[i] = piece;
+    }
+
+    /// move the piece in the square `from` to `to`, leaving `self[from]` empty
+    fn swap(&mut self, from: Square, to: Square) {
+        let piece = self[from];
+        self.put(from, Piece::None);
+        self.put(to, piece);
+    }
+
+    /// the side to move
+    pub fn to_move(&self) -> Color {
+        self.to_move
+    }
+
+    /// the number of the current full move, starting at 1
+    pub fn full_move(&self) -> usize {
+        self.full_move
+    }
+
+    /// find the unique legal move described by the SAN move `m`, returning
+    /// an error if there is no such move or more than one
+    pub(crate) fn resolve(&self, m: &Move) -> Result<LegalMove, IllegalMove> {
+        let c = self.to_move;
+        let candidates: Vec<_> = self
+            .pseudo_legal_moves(c)
+            .into_iter()
+            .filter(|mv| {
+                let Piece::Some { typ, .. } = self[mv.from] else {
+                    return false;
+                };
+                match m {
+                    Move::KingCastle => {
+                        self.is_castle(mv) && mv.to.1 > mv.from.1
                     }
-                    Color::White => {
-                        self.swap(E1, C1); // King
-                        self.swap(A1, D1); // Rook
-                        self.white_can_castle_kingside = false;
-                        self.white_can_castle_queenside = false;
+                    Move::QueenCastle => {
+                        self.is_castle(mv) && mv.to.1 < mv.from.1
                     }
-                }
-            }
-            Move::Normal {
-                typ: t,
-                from_rank,
-                from_file,
-                dest_rank,
-                dest_file,
-            } => {
-                for rank in 0..8 {
-                    for file in 0..8 {
-                        let p = self[(rank, file)];
-                        // skip empty square
-                        let Piece::Some { typ, color } = p else {
-                            continue;
-                        };
-                        // skip piece type or color mismatch
-                        if typ != *t || color != c {
-                            continue;
-                        }
-                        // skip from square mismatch
-                        if let Some(fr) = from_rank && *fr != rank {
-			    continue;
-			}
-                        if let Some(ff) = from_file && *ff != file {
-			    continue;
-			}
-                        // at this point we know the piece type, color, and
-                        // possibly from_rank/from_file are correct, so we just
-                        // have to verify that the piece on this square can make
-                        // a legal move to (dest_file, dest_rank)
-                        if p.can_move(
-                            self,
-                            rank,
-                            file,
-                            *dest_rank,
-                            *dest_file as usize,
-                        ) {
-                            // destination is occupied => capture; pawn move is
-                            // always an advance or capture
-                            if self[(*dest_rank, *dest_file as usize)].is_some()
-                                || typ.is_pawn()
-                            {
-                                self.half_move_clock = 0;
-                            } else {
-                                self.half_move_clock += 1;
-                            }
-                            self.swap(
-                                (rank, file),
-                                (*dest_rank, *dest_file as usize),
-                            );
-                            return;
-                        }
+                    Move::Normal {
+                        typ: t,
+                        from_rank,
+                        from_file,
+                        dest_rank,
+                        dest_file,
+                        promotion,
+                    } => {
+                        typ == *t
+                            && mv.to == (*dest_rank, *dest_file as usize)
+                            && from_rank.is_none_or(|r| r == mv.from.0)
+                            && from_file.is_none_or(|f| f == mv.from.1)
+                            && mv.promotion == *promotion
                     }
                 }
-                panic!("illegal move for {c}?: {m:?} on board:\n{self}");
-            }
+            })
+            .filter(|mv| self.is_legal(mv))
+            .collect();
+        match candidates[..] {
+            [mv] => Ok(mv),
+            _ => Err(IllegalMove {
+                ply: 2 * self.full_move - usize::from(c.is_white()),
+                san: m.to_string(),
+                fen: self.fen(),
+            }),
         }
     }
 
-    /// locate the king of `col` and determine its castling rights
-    fn fen_castle_field(&self) -> String {
-        let mut ret = String::new();
-        if self.white_can_castle_kingside {
-            ret.push('K')
+    /// play the SAN move `m`, returning the record needed to take it back
+    /// with [Board::unmake_move]
+    pub(crate) fn make_move(&mut self, m: &Move) -> Result<Undo, IllegalMove> {
+        let mv = self.resolve(m)?;
+        Ok(self.play(&mv))
+    }
+
+    /// apply the legal move `mv` to `self`, keeping the castling rights, en
+    /// passant target, clocks, and side to move up to date. the returned
+    /// record can be passed to [Board::unmake_move] to take the move back
+    pub fn play(&mut self, mv: &LegalMove) -> Undo {
+        let LegalMove {
+            from,
+            to,
+            promotion,
+        } = *mv;
+        let Piece::Some { typ, color } = self[from] else {
+            panic!("no piece to move from {from:?} on board:\n{self}");
+        };
+        let castle = self.is_castle(mv);
+        // a pawn capturing en passant takes the pawn it passed, and a
+        // castling king lands on its own rook without capturing it
+        let captured = if castle {
+            None
+        } else if self.is_en_passant(mv) {
+            Some((from.0, to.1))
+        } else {
+            self[to].is_some().then_some(to)
+        };
+        let undo = Undo {
+            mv: *mv,
+            captured: captured.map(|sq| (sq, self[sq])),
+            half_move_clock: self.half_move_clock,
+            en_passant_target: self.en_passant_target,
+            castling: self.castling,
+            castled: castle,
+            hash: self.hash,
+        };
+        self.hash ^= self.state_key();
+        // a pawn move is always an advance or capture
+        if captured.is_some() || typ.is_pawn() {
+            self.half_move_clock = 0;
+        } else {
+            self.half_move_clock += 1;
         }
-        if self.white_can_castle_queenside {
-            ret.push('Q')
+        if let Some(sq) = captured {
+            self.put(sq, Piece::None);
         }
-        if self.black_can_castle_kingside {
-            ret.push('k')
+        // a double pawn push leaves the skipped square as the next en passant
+        // target
+        self.en_passant_target = if typ.is_pawn() && from.0.abs_diff(to.0) == 2
+        {
+            Some(((from.0 + to.0) / 2, from.1))
+        } else {
+            None
+        };
+        self.update_castling_rights(typ, color, from, to);
+        if castle {
+            self.castle(mv);
+        } else {
+            self.swap(from, to);
         }
-        if self.black_can_castle_queenside {
-            ret.push('q')
+        if let Some(typ) = promotion {
+            self.put(to, Piece::Some { typ, color });
         }
-        if ret.is_empty() {
-            ret.push('-')
+        if color.is_black() {
+            self.full_move += 1;
         }
-        ret
+        self.to_move = color.other();
+        self.hash ^= self.state_key();
+        undo
     }
 
     /// return the FEN representation of `self`
-    pub(crate) fn fen(&self, half_move: usize) -> String {
+    pub(crate) fn fen(&self) -> String {
         let mut ret = String::new();
-        for (i, row) in self.board.iter().enumerate() {
+        for i in 0..8 {
             let mut empty = 0;
-            for p in row {
+            for file in 0..8 {
+                let p = self[(7 - i, file)];
                 if let Some(c) = p.to_char() {
                     if empty != 0 {
                         ret.push(char::from_digit(empty, 10).unwrap());
@@ -319,10 +481,9 @@ impl Board {
                 ret.push(' ');
             }
         }
-        if (half_move & 1) == 0 {
-            ret.push('w');
-        } else {
-            ret.push('b');
+        match self.to_move {
+            Color::Black => ret.push('b'),
+            Color::White => ret.push('w'),
         }
         ret.push(' ');
 
@@ -330,29 +491,15 @@ impl Board {
 
         ret.push(' ');
 
-        if let Some((rank, file)) = self.en_passant_target {
-            ret.push(match file {
-                0 => 'a',
-                1 => 'b',
-                2 => 'c',
-                3 => 'd',
-                4 => 'e',
-                5 => 'f',
-                6 => 'g',
-                7 => 'h',
-                _ => unreachable!(),
-            });
-            ret.push(char::from_digit(rank as u32, 10).unwrap());
+        if let Some(sq) = self.en_passant_target {
+            ret.push_str(&fen::square_name(sq));
         } else {
             ret.push('-');
         }
 
-        ret.push(' ');
-        ret.push(char::from_digit(self.half_move_clock as u32, 10).unwrap());
-
-        ret.push(' ');
         use std::fmt::Write;
-        write!(ret, "{}", half_move / 2 + 1).unwrap();
+        write!(ret, " {}", self.half_move_clock).unwrap();
+        write!(ret, " {}", self.full_move).unwrap();
         ret
     }
 }
@@ -0,0 +1,49 @@
+use super::{
+    bitboard::{
+        bishop_attacks, index, rook_attacks, Bitboard, Squares, KING_ATTACKS,
+        KNIGHT_ATTACKS, PAWN_ATTACKS,
+    },
+    Board, Color, PieceType, Square,
+};
+
+impl Board {
+    /// the pieces of color `by` attacking square `i`, with sliding pieces
+    /// blocked by the `occupied` squares and ignoring any pieces not in it.
+    /// this counts attacks on squares holding pieces of either color, so it
+    /// covers defenders too
+    pub(crate) fn attackers_to(
+        &self,
+        i: usize,
+        by: Color,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        use PieceType::*;
+        let queens = self.bitboard(Queen, by);
+        // a pawn of `by` attacks `i` from the squares a pawn of the other
+        // color would attack from `i`
+        let attackers = KNIGHT_ATTACKS[i] & self.bitboard(Knight, by)
+            | KING_ATTACKS[i] & self.bitboard(King, by)
+            | PAWN_ATTACKS[by.other().index()][i] & self.bitboard(Pawn, by)
+            | bishop_attacks(i, occupied)
+                & (self.bitboard(Bishop, by) | queens)
+            | rook_attacks(i, occupied) & (self.bitboard(Rook, by) | queens);
+        // pieces missing from `occupied` have already been traded off
+        attackers & occupied
+    }
+
+    /// the squares of the pieces of `color` that attack `sq`, from a1 to h8
+    pub fn attackers(
+        &self,
+        sq: impl Into<Square>,
+        color: Color,
+    ) -> Vec<Square> {
+        let i = index(sq.into());
+        Squares(self.attackers_to(i, color, self.occupied())).collect()
+    }
+
+    /// report whether any piece of color `by` attacks `sq`
+    pub fn is_attacked(&self, sq: impl Into<Square>, by: Color) -> bool {
+        let i = index(sq.into());
+        self.attackers_to(i, by, self.occupied()) != 0
+    }
+}
@@ -0,0 +1,178 @@
+use super::{Color, Square};
+
+/// a set of squares, with bit `8 * rank + file` set for each square present
+pub(crate) type Bitboard = u64;
+
+/// the index of `sq` into a [Bitboard] or [super::Board::mailbox]
+pub(crate) const fn index((rank, file): Square) -> usize {
+    8 * rank + file
+}
+
+/// the inverse of [index]
+pub(crate) const fn square(i: usize) -> Square {
+    (i / 8, i % 8)
+}
+
+/// the [Bitboard] containing only `sq`
+pub(crate) const fn bit(sq: Square) -> Bitboard {
+    1 << index(sq)
+}
+
+/// the dark squares, starting with a1
+pub(crate) const DARK_SQUARES: Bitboard = 0xaa55aa55aa55aa55;
+
+/// iterate over the squares in a [Bitboard], from a1 to h8
+pub(crate) struct Squares(pub(crate) Bitboard);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let i = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(square(i))
+    }
+}
+
+const KNIGHT: [(isize, isize); 8] = [
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+];
+
+const KING: [(isize, isize); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// the directions in which the sliding pieces move. the first four increase
+/// the square index and the last four decrease it
+const DIRECTIONS: [(isize, isize); 8] = [
+    (1, 0),
+    (0, 1),
+    (1, 1),
+    (1, -1),
+    (-1, 0),
+    (0, -1),
+    (-1, -1),
+    (-1, 1),
+];
+
+/// the indices into [DIRECTIONS] of the rook and bishop directions
+const LINES: [usize; 4] = [0, 1, 4, 5];
+const DIAGONALS: [usize; 4] = [2, 3, 6, 7];
+
+/// return the square `(dr, df)` away from square `i` if it's still on the
+/// board
+const fn offset(i: usize, (dr, df): (isize, isize)) -> Option<usize> {
+    let (rank, file) = square(i);
+    let rank = rank as isize + dr;
+    let file = file as isize + df;
+    if rank < 0 || rank >= 8 || file < 0 || file >= 8 {
+        return None;
+    }
+    Some(index((rank as usize, file as usize)))
+}
+
+/// build a table of the squares one step away from each square along any of
+/// `offsets`
+const fn jumps(offsets: &[(isize, isize)]) -> [Bitboard; 64] {
+    let mut ret = [0; 64];
+    let mut i = 0;
+    while i < 64 {
+        let mut o = 0;
+        while o < offsets.len() {
+            if let Some(to) = offset(i, offsets[o]) {
+                ret[i] |= 1 << to;
+            }
+            o += 1;
+        }
+        i += 1;
+    }
+    ret
+}
+
+/// build the table of the squares along each of [DIRECTIONS] from each square,
+/// up to the edge of the board
+const fn rays() -> [[Bitboard; 64]; 8] {
+    let mut ret = [[0; 64]; 8];
+    let mut d = 0;
+    while d < 8 {
+        let mut i = 0;
+        while i < 64 {
+            let mut cur = i;
+            while let Some(to) = offset(cur, DIRECTIONS[d]) {
+                ret[d][i] |= 1 << to;
+                cur = to;
+            }
+            i += 1;
+        }
+        d += 1;
+    }
+    ret
+}
+
+pub(crate) const KNIGHT_ATTACKS: [Bitboard; 64] = jumps(&KNIGHT);
+
+pub(crate) const KING_ATTACKS: [Bitboard; 64] = jumps(&KING);
+
+/// the squares attacked by a pawn of each color, indexed by
+/// [Color::index] and then by square
+pub(crate) const PAWN_ATTACKS: [[Bitboard; 64]; 2] =
+    [jumps(&[(1, 1), (1, -1)]), jumps(&[(-1, 1), (-1, -1)])];
+
+const RAYS: [[Bitboard; 64]; 8] = rays();
+
+/// the squares attacked from square `i` along the directions in `dirs`, given
+/// the `occupied` squares. each ray stops at the first occupied square,
+/// including it
+fn slider_attacks(i: usize, occupied: Bitboard, dirs: [usize; 4]) -> Bitboard {
+    let mut ret = 0;
+    for d in dirs {
+        let ray = RAYS[d][i];
+        let blockers = ray & occupied;
+        if blockers == 0 {
+            ret |= ray;
+            continue;
+        }
+        let first = if d < 4 {
+            blockers.trailing_zeros()
+        } else {
+            63 - blockers.leading_zeros()
+        };
+        ret |= ray ^ RAYS[d][first as usize];
+    }
+    ret
+}
+
+pub(crate) fn bishop_attacks(i: usize, occupied: Bitboard) -> Bitboard {
+    slider_attacks(i, occupied, DIAGONALS)
+}
+
+pub(crate) fn rook_attacks(i: usize, occupied: Bitboard) -> Bitboard {
+    slider_attacks(i, occupied, LINES)
+}
+
+impl Color {
+    /// the index of `self` into per-color tables like [PAWN_ATTACKS]
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use super::{
+    movegen::back_rank, piece::Piece, Board, Color, LegalMove, PieceType,
+    Square,
+};
+
+/// the index into [Board::castling] for `color` castling on the king's side
+/// if `kingside` and on the queen's side otherwise
+pub(crate) fn castling_index(color: Color, kingside: bool) -> usize {
+    2 * color.index() + usize::from(!kingside)
+}
+
+/// the files the king and rook end up on after castling, which are the same
+/// in Chess960 as in standard chess
+pub(crate) fn castled_files(kingside: bool) -> (usize, usize) {
+    if kingside {
+        (6, 5)
+    } else {
+        (2, 3)
+    }
+}
+
+/// the color whose right is stored at index `i` of [Board::castling]
+fn castling_color(i: usize) -> Color {
+    if i < 2 {
+        Color::White
+    } else {
+        Color::Black
+    }
+}
+
+impl Board {
+    /// report whether the game is Chess960, which only affects how castling
+    /// moves are written in UCI notation
+    pub fn chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    /// report whether `mv` castles, which is encoded as the king capturing
+    /// its own rook
+    pub(crate) fn is_castle(&self, mv: &LegalMove) -> bool {
+        match (self[mv.from], self[mv.to]) {
+            (
+                Piece::Some {
+                    typ: PieceType::King,
+                    color,
+                },
+                Piece::Some {
+                    typ: PieceType::Rook,
+                    color: rook,
+                },
+            ) => color == rook,
+            _ => false,
+        }
+    }
+
+    /// castling requires the right to castle with a rook, that every square
+    /// either the king or rook crosses or lands on be empty apart from the
+    /// king and rook themselves, and that the king not start in, pass
+    /// through, or land on an attacked square
+    pub(crate) fn castle_moves(
+        &self,
+        king: Square,
+        color: Color,
+        ret: &mut Vec<LegalMove>,
+    ) {
+        let rank = back_rank(color);
+        if king.0 != rank {
+            return;
+        }
+        let enemy = color.other();
+        for kingside in [true, false] {
+            let Some(file) = self.castling[castling_index(color, kingside)]
+            else {
+                continue;
+            };
+            if !self.holds((rank, file), PieceType::Rook, color) {
+                continue;
+            }
+            let (king_to, rook_to) = castled_files(kingside);
+            let files = [king.1, file, king_to, rook_to];
+            let lo = files.into_iter().min().unwrap();
+            let hi = files.into_iter().max().unwrap();
+            let clear = (lo..=hi)
+                .all(|f| f == king.1 || f == file || self[(rank, f)].is_none());
+            let path = king.1.min(king_to)..=king.1.max(king_to);
+            if clear
+                && path
+                    .into_iter()
+                    .all(|f| !self.is_attacked((rank, f), enemy))
+            {
+                ret.push(LegalMove::new(king, (rank, file)));
+            }
+        }
+    }
+
+    /// move the king and rook for the castling move `mv`
+    pub(crate) fn castle(&mut self, mv: &LegalMove) {
+        let (king_to, rook_to) = castled_files(mv.to.1 > mv.from.1);
+        let rank = mv.from.0;
+        let king = self[mv.from];
+        let rook = self[mv.to];
+        self.put(mv.from, Piece::None);
+        self.put(mv.to, Piece::None);
+        self.put((rank, king_to), king);
+        self.put((rank, rook_to), rook);
+    }
+
+    /// take back the castling move `mv`, the reverse of [Board::castle]
+    pub(crate) fn uncastle(&mut self, mv: &LegalMove) {
+        let (king_to, rook_to) = castled_files(mv.to.1 > mv.from.1);
+        let rank = mv.from.0;
+        let king = self[(rank, king_to)];
+        let rook = self[(rank, rook_to)];
+        self.put((rank, king_to), Piece::None);
+        self.put((rank, rook_to), Piece::None);
+        self.put(mv.from, king);
+        self.put(mv.to, rook);
+    }
+
+    /// moving the king gives up both castling rights, while moving a
+    /// castling rook or capturing one gives up the right on that side
+    pub(crate) fn update_castling_rights(
+        &mut self,
+        typ: PieceType,
+        color: Color,
+        from: Square,
+        to: Square,
+    ) {
+        for (i, right) in self.castling.iter_mut().enumerate() {
+            let Some(file) = *right else {
+                continue;
+            };
+            let c = castling_color(i);
+            let rook = (back_rank(c), file);
+            if typ.is_king() && color == c || rook == from || rook == to {
+                *right = None;
+            }
+        }
+    }
+
+    /// the castling field of the FEN for `self`. this uses `KQkq` for rooks
+    /// with no other rook further out on the same side, like X-FEN, and the
+    /// rook's file otherwise
+    pub(crate) fn fen_castle_field(&self) -> String {
+        let mut ret = String::new();
+        for (i, right) in self.castling.iter().enumerate() {
+            let Some(file) = *right else {
+                continue;
+            };
+            let color = castling_color(i);
+            let rank = back_rank(color);
+            let kingside = (i & 1) == 0;
+            let mut outside = if kingside { file + 1..8 } else { 0..file };
+            let c = if outside
+                .any(|f| self.holds((rank, f), PieceType::Rook, color))
+            {
+                (b'a' + file as u8) as char
+            } else if kingside {
+                'k'
+            } else {
+                'q'
+            };
+            if color.is_white() {
+                ret.push(c.to_ascii_uppercase());
+            } else {
+                ret.push(c);
+            }
+        }
+        if ret.is_empty() {
+            ret.push('-')
+        }
+        ret
+    }
+
+    /// parse the castling field of a FEN for the pieces on `self`. this
+    /// accepts standard `KQkq`, where a right belongs to the outermost rook
+    /// on that side of the king, which has to be there, and the rook files of
+    /// Shredder-FEN and X-FEN, like `HAha`
+    pub(crate) fn parse_castle_field(
+        &self,
+        s: &str,
+    ) -> Option<[Option<usize>; 4]> {
+        let mut ret = [None; 4];
+        if s == "-" {
+            return Some(ret);
+        }
+        if s.is_empty() {
+            return None;
+        }
+        for c in s.chars() {
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let rank = back_rank(color);
+            let king = self.king(color).filter(|k| k.0 == rank)?.1;
+            let is_rook =
+                |f: &usize| self.holds((rank, *f), PieceType::Rook, color);
+            let (kingside, file) = match c.to_ascii_lowercase() {
+                'k' => (true, (king + 1..8).rev().find(is_rook)?),
+                'q' => (false, (0..king).find(is_rook)?),
+                f @ 'a'..='h' => {
+                    let f = (f as u8 - b'a') as usize;
+                    (f > king, f)
+                }
+                _ => return None,
+            };
+            let i = castling_index(color, kingside);
+            if file == king || ret[i].is_some() {
+                return None;
+            }
+            ret[i] = Some(file);
+        }
+        Some(ret)
+    }
+
+    /// report whether every castling right is for a king on the e-file and a
+    /// rook in the corner, as in standard chess
+    pub(crate) fn has_standard_castling(&self) -> bool {
+        self.castling.iter().enumerate().all(|(i, right)| {
+            let Some(file) = *right else {
+                return true;
+            };
+            let color = castling_color(i);
+            let corner = if (i & 1) == 0 { 7 } else { 0 };
+            file == corner && self.king(color) == Some((back_rank(color), 4))
+        })
+    }
+}
@@ -4,9 +4,9 @@ use std::fmt::Display;
 
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.board {
-            for p in row {
-                write!(f, "{p}")?;
+        for rank in (0..8).rev() {
+            for file in 0..8 {
+                write!(f, "{}", self[(rank, file)])?;
             }
             writeln!(f)?;
         }
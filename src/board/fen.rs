@@ -0,0 +1,211 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{file::File, piece::Piece, Board, Color, PieceType, Square};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FenError {
+    /// the FEN did not contain exactly six space-separated fields
+    FieldCount(usize),
+
+    /// the piece placement field did not describe 8 ranks of 8 squares
+    Placement(String),
+
+    /// a character in the piece placement field was neither a piece nor a
+    /// count of empty squares
+    Piece(char),
+
+    /// each side needs exactly one king
+    Kings,
+
+    /// pawns can never stand on the first or last rank
+    PawnOnBackRank,
+
+    SideToMove(String),
+    Castling(String),
+    EnPassant(String),
+    HalfMoveClock(String),
+    FullMoveNumber(String),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::FieldCount(n) => {
+                write!(f, "expected 6 fields in FEN, found {n}")
+            }
+            FenError::Placement(s) => {
+                write!(f, "invalid piece placement field `{s}`")
+            }
+            FenError::Piece(c) => write!(f, "invalid piece `{c}`"),
+            FenError::Kings => write!(f, "each side must have one king"),
+            FenError::PawnOnBackRank => {
+                write!(f, "pawns cannot be on the first or last rank")
+            }
+            FenError::SideToMove(s) => write!(f, "invalid side to move `{s}`"),
+            FenError::Castling(s) => {
+                write!(f, "invalid castling availability `{s}`")
+            }
+            FenError::EnPassant(s) => {
+                write!(f, "invalid en passant target `{s}`")
+            }
+            FenError::HalfMoveClock(s) => {
+                write!(f, "invalid half-move clock `{s}`")
+            }
+            FenError::FullMoveNumber(s) => {
+                write!(f, "invalid full-move number `{s}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// parse an algebraic square like `e3`
+pub(crate) fn parse_square(s: &str) -> Option<Square> {
+    let mut chars = s.chars();
+    let file = File::try_from(chars.next()?).ok()?;
+    let rank = chars.next()?.to_digit(10)? as usize;
+    if chars.next().is_some() || !(1..=8).contains(&rank) {
+        return None;
+    }
+    Some((rank - 1, file as usize))
+}
+
+/// the algebraic name of `sq`, like `e3`. the inverse of [parse_square]
+pub(crate) fn square_name((rank, file): Square) -> String {
+    format!("{}{}", (b'a' + file as u8) as char, rank + 1)
+}
+
+/// parse the piece placement field into the `rows` argument of
+/// [Board::set_placement], starting from the eighth rank
+fn parse_placement(s: &str) -> Result<[[Piece; 8]; 8], FenError> {
+    let mut board = [[Piece::None; 8]; 8];
+    let ranks: Vec<_> = s.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::Placement(s.to_owned()));
+    }
+    for (row, rank) in board.iter_mut().zip(ranks) {
+        let mut file = 0;
+        for c in rank.chars() {
+            if let Some(n) = c.to_digit(10)
+                && (1..=8).contains(&n)
+            {
+                file += n as usize;
+            } else {
+                let p = Piece::from_char(c).ok_or(FenError::Piece(c))?;
+                if file < 8 {
+                    row[file] = p;
+                }
+                file += 1;
+            }
+        }
+        if file != 8 {
+            return Err(FenError::Placement(s.to_owned()));
+        }
+    }
+
+    for color in [Color::White, Color::Black] {
+        let king = Piece::Some {
+            typ: PieceType::King,
+            color,
+        };
+        if board.iter().flatten().filter(|&&p| p == king).count() != 1 {
+            return Err(FenError::Kings);
+        }
+    }
+    let back_ranks = [board[0], board[7]];
+    if back_ranks
+        .iter()
+        .flatten()
+        .any(|p| matches!(p.to_char(), Some('p' | 'P')))
+    {
+        return Err(FenError::PawnOnBackRank);
+    }
+
+    Ok(board)
+}
+
+impl Board {
+    /// parse a [Board] from the FEN string `s`
+    pub fn from_fen(s: &str) -> Result<Self, FenError> {
+        let fields: Vec<_> = s.split_ascii_whitespace().collect();
+        let [placement, side, castling, en_passant, clock, full_move] =
+            fields[..]
+        else {
+            return Err(FenError::FieldCount(fields.len()));
+        };
+
+        let board = parse_placement(placement)?;
+
+        let to_move = match side {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::SideToMove(side.to_owned())),
+        };
+
+        // the castling rights depend on where the kings and rooks stand
+        let mut ret = Self {
+            to_move,
+            ..Self::empty()
+        };
+        ret.set_placement(board);
+        ret.castling = ret
+            .parse_castle_field(castling)
+            .ok_or_else(|| FenError::Castling(castling.to_owned()))?;
+        ret.chess960 = !ret.has_standard_castling();
+
+        let en_passant_target = match en_passant {
+            "-" => None,
+            _ => {
+                // the target is behind the pawn that just moved, so it's on
+                // the third rank after White moves and the sixth after Black,
+                // with the pawn in front of it and the square the pawn came
+                // from empty
+                let (want, pawn, from) = match to_move {
+                    Color::Black => (2, 3, 1),
+                    Color::White => (5, 4, 6),
+                };
+                match parse_square(en_passant) {
+                    Some((rank, file))
+                        if rank == want
+                            && ret[(rank, file)] == Piece::None
+                            && ret[(from, file)] == Piece::None
+                            && ret.holds(
+                                (pawn, file),
+                                PieceType::Pawn,
+                                to_move.other(),
+                            ) =>
+                    {
+                        Some((rank, file))
+                    }
+                    _ => {
+                        return Err(FenError::EnPassant(en_passant.to_owned()))
+                    }
+                }
+            }
+        };
+
+        let half_move_clock = clock
+            .parse()
+            .map_err(|_| FenError::HalfMoveClock(clock.to_owned()))?;
+
+        let full_move: usize = match full_move.parse() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(FenError::FullMoveNumber(full_move.to_owned())),
+        };
+
+        ret.half_move_clock = half_move_clock;
+        ret.full_move = full_move;
+        ret.en_passant_target = en_passant_target;
+        ret.hash = ret.compute_hash();
+        Ok(ret)
+    }
+}
+
+impl FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_fen(s)
+    }
+}
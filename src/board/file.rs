@@ -11,12 +11,6 @@ pub enum File {
     H = 7,
 }
 
-impl File {
-    pub fn from_unchecked(value: char) -> Self {
-        Self::try_from(value).unwrap()
-    }
-}
-
 impl TryFrom<char> for File {
     type Error = ();
 
@@ -49,3 +43,20 @@ impl From<File> for char {
         }
     }
 }
+
+impl File {
+    /// the file the same distance from the other edge of the board, like the
+    /// h-file for the a-file
+    pub fn mirrored(self) -> Self {
+        match self {
+            File::A => File::H,
+            File::B => File::G,
+            File::C => File::F,
+            File::D => File::E,
+            File::E => File::D,
+            File::F => File::C,
+            File::G => File::B,
+            File::H => File::A,
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use super::{piece::Piece, Board};
-use std::ops::{Index, IndexMut};
+use super::{bitboard, piece::Piece, Board};
+use std::ops::Index;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Coord {
     A1,
     A2,
@@ -218,19 +219,6 @@ where
     type Output = Piece;
 
     fn index(&self, t: T) -> &Self::Output {
-        let (i, j) = t.into();
-        let i = 7 - i;
-        &self.board[i][j]
-    }
-}
-
-impl<T> IndexMut<T> for Board
-where
-    T: Into<(usize, usize)>,
-{
-    fn index_mut(&mut self, t: T) -> &mut Self::Output {
-        let (i, j) = t.into();
-        let i = 7 - i;
-        &mut self.board[i][j]
+        &self.mailbox[bitboard::index(t.into())]
     }
 }
@@ -0,0 +1,170 @@
+use std::fmt::Display;
+
+use super::{bitboard::Bitboard, movegen::back_rank, Board, Color, PieceType};
+
+/// the pieces one side has left, other than its king
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Side {
+    pub queens: u32,
+    pub rooks: u32,
+    pub bishops: u32,
+    pub knights: u32,
+    pub pawns: u32,
+}
+
+impl Side {
+    /// the number of pieces of type `typ`, which is always 0 for kings
+    pub fn count(&self, typ: PieceType) -> u32 {
+        match typ {
+            PieceType::King => 0,
+            PieceType::Queen => self.queens,
+            PieceType::Rook => self.rooks,
+            PieceType::Bishop => self.bishops,
+            PieceType::Knight => self.knights,
+            PieceType::Pawn => self.pawns,
+        }
+    }
+
+    /// the total value of the pieces in pawns, by [PieceType::value]
+    pub fn points(&self) -> i32 {
+        use PieceType::*;
+        [Queen, Rook, Bishop, Knight, Pawn]
+            .into_iter()
+            .map(|typ| self.count(typ) as i32 * typ.value())
+            .sum()
+    }
+
+    /// the number of queens, rooks, bishops, and knights
+    pub fn pieces(&self) -> u32 {
+        self.queens + self.rooks + self.bishops + self.knights
+    }
+}
+
+/// the material each side has on the board
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Material {
+    pub white: Side,
+    pub black: Side,
+}
+
+impl Material {
+    pub fn side(&self, color: Color) -> &Side {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    /// White's points minus Black's, so positive when White is ahead
+    pub fn balance(&self) -> i32 {
+        self.white.points() - self.black.points()
+    }
+
+    /// the imbalances worth pointing out next to the engine's evaluation,
+    /// White's first
+    pub fn imbalances(&self) -> Vec<Imbalance> {
+        let mut ret = Vec::new();
+        for color in [Color::White, Color::Black] {
+            let (us, them) = (self.side(color), self.side(color.other()));
+            if us.bishops >= 2 && them.bishops < 2 {
+                ret.push(Imbalance::BishopPair(color));
+            }
+            // a minor piece for a rook, with the other pieces even
+            let minors = |s: &Side| s.bishops + s.knights;
+            if us.rooks + 1 == them.rooks
+                && minors(us) == minors(them) + 1
+                && us.queens == them.queens
+            {
+                ret.push(Imbalance::ExchangeDown(color));
+            }
+        }
+        ret
+    }
+}
+
+/// a notable difference between the two sides' material
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Imbalance {
+    /// the side has two bishops and the other doesn't
+    BishopPair(Color),
+
+    /// the side has a minor piece where the other has a rook
+    ExchangeDown(Color),
+}
+
+impl Display for Imbalance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = |c: &Color| match c {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+        match self {
+            Imbalance::BishopPair(c) => {
+                write!(f, "{} has the bishop pair", name(c))
+            }
+            Imbalance::ExchangeDown(c) => {
+                write!(f, "{} is the exchange down", name(c))
+            }
+        }
+    }
+}
+
+/// the stage of the game, for splitting up statistics
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Phase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Opening => write!(f, "opening"),
+            Phase::Middlegame => write!(f, "middlegame"),
+            Phase::Endgame => write!(f, "endgame"),
+        }
+    }
+}
+
+impl Board {
+    /// count the material each side has left
+    pub fn material(&self) -> Material {
+        let side = |color| {
+            let count = |typ| self.bitboard(typ, color).count_ones();
+            Side {
+                queens: count(PieceType::Queen),
+                rooks: count(PieceType::Rook),
+                bishops: count(PieceType::Bishop),
+                knights: count(PieceType::Knight),
+                pawns: count(PieceType::Pawn),
+            }
+        };
+        Material {
+            white: side(Color::White),
+            black: side(Color::Black),
+        }
+    }
+
+    /// classify the position by how much material is left and how far the
+    /// pieces have developed, roughly like Lichess. it's an endgame once at
+    /// most 6 queens, rooks, bishops, and knights remain, and a middlegame
+    /// once at most 10 remain or either side has fewer than 4 pieces left on
+    /// its back rank
+    pub fn phase(&self) -> Phase {
+        let material = self.material();
+        let pieces = material.white.pieces() + material.black.pieces();
+        if pieces <= 6 {
+            return Phase::Endgame;
+        }
+        let developed = [Color::White, Color::Black].into_iter().any(|color| {
+            let rank: Bitboard = 0xff << (8 * back_rank(color));
+            (self.colors[color.index()] & rank).count_ones() < 4
+        });
+        if pieces <= 10 || developed {
+            Phase::Middlegame
+        } else {
+            Phase::Opening
+        }
+    }
+}
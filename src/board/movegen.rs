@@ -0,0 +1,197 @@
+use std::fmt::Display;
+
+use super::{
+    bitboard::{
+        bishop_attacks, bit, index, rook_attacks, Squares, KING_ATTACKS,
+        KNIGHT_ATTACKS, PAWN_ATTACKS,
+    },
+    fen::square_name,
+    piece::Piece,
+    Board, Color, PieceType, Square,
+};
+
+/// a fully-specified move from one square to another, as opposed to the
+/// partial description in a SAN [crate::pgn::mov::Move]. castling is encoded
+/// as the king capturing its own rook, which works for Chess960 too
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LegalMove {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceType>,
+}
+
+impl LegalMove {
+    pub fn new(from: Square, to: Square) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+}
+
+/// the long algebraic form used by UCI engines, like `e2e4` or `e7e8q`. see
+/// [Board::uci_move] for writing castling moves the way engines expect
+impl Display for LegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", square_name(self.from), square_name(self.to))?;
+        if let Some(p) = self.promotion {
+            write!(f, "{}", char::from(p).to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+/// the rank on which pieces of `color` start, and so where they castle
+pub(crate) fn back_rank(color: Color) -> usize {
+    match color {
+        Color::Black => 7,
+        Color::White => 0,
+    }
+}
+
+impl Board {
+    /// report whether `sq` holds a piece of type `typ` and color `color`
+    pub(crate) fn holds(
+        &self,
+        sq: Square,
+        typ: PieceType,
+        color: Color,
+    ) -> bool {
+        self[sq] == Piece::Some { typ, color }
+    }
+
+    /// report whether `mv` captures en passant, which is a pawn moving
+    /// diagonally onto an empty square
+    pub(crate) fn is_en_passant(&self, mv: &LegalMove) -> bool {
+        matches!(self[mv.from], Piece::Some { typ, .. } if typ.is_pawn())
+            && mv.from.1 != mv.to.1
+            && self[mv.to].is_none()
+    }
+
+    /// locate the king of `color`
+    pub(crate) fn king(&self, color: Color) -> Option<Square> {
+        Squares(self.bitboard(PieceType::King, color)).next()
+    }
+
+    /// report whether the king of `color` is currently attacked
+    pub(crate) fn in_check(&self, color: Color) -> bool {
+        self.king(color)
+            .is_some_and(|king| self.is_attacked(king, color.other()))
+    }
+
+    /// generate the moves available to `color` without considering whether
+    /// they leave its own king in check
+    pub(crate) fn pseudo_legal_moves(&self, color: Color) -> Vec<LegalMove> {
+        use PieceType::*;
+        let mut ret = Vec::new();
+        let own = self.colors[color.index()];
+        let occupied = self.occupied();
+        for from in Squares(self.bitboard(Pawn, color)) {
+            self.pawn_moves(from, color, &mut ret);
+        }
+        for typ in [Knight, Bishop, Rook, Queen, King] {
+            for from in Squares(self.bitboard(typ, color)) {
+                let i = index(from);
+                let attacks = match typ {
+                    Knight => KNIGHT_ATTACKS[i],
+                    Bishop => bishop_attacks(i, occupied),
+                    Rook => rook_attacks(i, occupied),
+                    Queen => {
+                        bishop_attacks(i, occupied) | rook_attacks(i, occupied)
+                    }
+                    King => KING_ATTACKS[i],
+                    Pawn => unreachable!(),
+                };
+                ret.extend(
+                    Squares(attacks & !own).map(|to| LegalMove::new(from, to)),
+                );
+            }
+        }
+        if let Some(king) = self.king(color) {
+            self.castle_moves(king, color, &mut ret);
+        }
+        ret
+    }
+
+    fn pawn_moves(&self, from: Square, color: Color, ret: &mut Vec<LegalMove>) {
+        let (start_rank, forward) = match color {
+            Color::Black => (6, -1),
+            Color::White => (1, 1),
+        };
+        let last_rank = back_rank(color.other());
+        let mut push = |to: Square| {
+            if to.0 == last_rank {
+                for p in PROMOTIONS {
+                    ret.push(LegalMove {
+                        from,
+                        to,
+                        promotion: Some(p),
+                    });
+                }
+            } else {
+                ret.push(LegalMove::new(from, to));
+            }
+        };
+        // pawns never stand on the last rank, so there's always a square
+        // ahead
+        let one = (from.0.wrapping_add_signed(forward), from.1);
+        if self[one].is_none() {
+            push(one);
+            let two = (one.0.wrapping_add_signed(forward), from.1);
+            if from.0 == start_rank && self[two].is_none() {
+                push(two);
+            }
+        }
+        let mut targets = self.colors[color.other().index()];
+        if let Some(ep) = self.en_passant_target {
+            targets |= bit(ep);
+        }
+        for to in Squares(PAWN_ATTACKS[color.index()][index(from)] & targets) {
+            push(to);
+        }
+    }
+
+    /// report whether the pseudo-legal move `mv` leaves the mover's king safe
+    pub(crate) fn is_legal(&self, mv: &LegalMove) -> bool {
+        let mut board = self.clone();
+        board.play(mv);
+        !board.in_check(self.to_move)
+    }
+
+    /// generate all of the legal moves available to the side to move
+    pub fn legal_moves(&self) -> Vec<LegalMove> {
+        let color = self.to_move;
+        let mut moves = self.pseudo_legal_moves(color);
+        let mut board = self.clone();
+        moves.retain(|mv| {
+            let undo = board.play(mv);
+            let legal = !board.in_check(color);
+            board.unmake_move(&undo);
+            legal
+        });
+        moves
+    }
+
+    /// report whether the side to move is in check
+    pub fn is_check(&self) -> bool {
+        self.in_check(self.to_move)
+    }
+
+    /// report whether the side to move is in check with no legal moves
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_moves().is_empty()
+    }
+
+    /// report whether the side to move has no legal moves but is not in check
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && self.legal_moves().is_empty()
+    }
+}
@@ -0,0 +1,42 @@
+use super::{Board, LegalMove};
+
+impl Board {
+    /// count the leaf nodes of the tree of legal moves `depth` plies deep,
+    /// for checking the move generator against known totals
+    pub fn perft(&self, depth: usize) -> u64 {
+        self.clone().count_leaves(depth)
+    }
+
+    /// split the [Board::perft] count for `depth` by the first move, which
+    /// helps to find the line where a miscounting move generator goes wrong
+    pub fn divide(&self, depth: usize) -> Vec<(LegalMove, u64)> {
+        let mut board = self.clone();
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let undo = board.play(&mv);
+                let n = board.count_leaves(depth.saturating_sub(1));
+                board.unmake_move(&undo);
+                (mv, n)
+            })
+            .collect()
+    }
+
+    /// the recursive part of [Board::perft], making and unmaking moves on
+    /// `self` instead of cloning it at every node
+    fn count_leaves(&mut self, depth: usize) -> u64 {
+        match depth {
+            0 => 1,
+            1 => self.legal_moves().len() as u64,
+            _ => {
+                let mut ret = 0;
+                for mv in self.legal_moves() {
+                    let undo = self.play(&mv);
+                    ret += self.count_leaves(depth - 1);
+                    self.unmake_move(&undo);
+                }
+                ret
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use super::{bitboard::DARK_SQUARES, Board, IllegalMove, PieceType};
+use crate::pgn::mov::Move;
+
+/// the ways a game can be drawn other than by agreement
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Draw {
+    Stalemate,
+
+    /// neither side has enough material left to checkmate
+    InsufficientMaterial,
+
+    /// the same position occurred five times, ending the game automatically
+    FivefoldRepetition,
+
+    /// 75 moves by each side without a pawn move or capture, ending the game
+    /// automatically
+    SeventyFiveMoveRule,
+
+    /// the same position occurred three times, so either side may claim a draw
+    ThreefoldRepetition,
+
+    /// 50 moves by each side without a pawn move or capture, so either side
+    /// may claim a draw
+    FiftyMoveRule,
+}
+
+impl Draw {
+    /// report whether the draw ends the game without either player claiming
+    /// it
+    pub fn is_automatic(&self) -> bool {
+        !matches!(self, Self::ThreefoldRepetition | Self::FiftyMoveRule)
+    }
+}
+
+impl Display for Draw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Draw::Stalemate => write!(f, "stalemate"),
+            Draw::InsufficientMaterial => write!(f, "insufficient material"),
+            Draw::FivefoldRepetition => write!(f, "fivefold repetition"),
+            Draw::SeventyFiveMoveRule => write!(f, "the 75-move rule"),
+            Draw::ThreefoldRepetition => write!(f, "threefold repetition"),
+            Draw::FiftyMoveRule => write!(f, "the 50-move rule"),
+        }
+    }
+}
+
+impl Board {
+    /// the number of half moves since the last pawn move or capture
+    pub fn half_move_clock(&self) -> usize {
+        self.half_move_clock
+    }
+
+    /// report whether neither side can possibly checkmate: bare kings, a
+    /// single minor piece, or only bishops that all stand on the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        use PieceType::*;
+        let heavy = self.pieces[Pawn as usize]
+            | self.pieces[Rook as usize]
+            | self.pieces[Queen as usize];
+        if heavy != 0 {
+            return false;
+        }
+        let bishops = self.pieces[Bishop as usize];
+        let minors = bishops | self.pieces[Knight as usize];
+        minors.count_ones() <= 1
+            || minors == bishops
+                && (bishops & DARK_SQUARES == 0 || bishops & !DARK_SQUARES == 0)
+    }
+}
+
+/// a [Board] along with the positions it has passed through, for detecting
+/// draws while replaying a game
+#[derive(Clone)]
+pub struct Replay {
+    board: Board,
+
+    /// the hashes of the positions reached since the last pawn move or
+    /// capture, including the current one. earlier positions can never occur
+    /// again
+    history: Vec<u64>,
+}
+
+impl Replay {
+    pub fn new(board: Board) -> Self {
+        let history = vec![board.hash()];
+        Self { board, history }
+    }
+
+    /// the current position
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub(crate) fn make_move(&mut self, m: &Move) -> Result<(), IllegalMove> {
+        self.board.make_move(m)?;
+        if self.board.half_move_clock == 0 {
+            self.history.clear();
+        }
+        self.history.push(self.board.hash());
+        Ok(())
+    }
+
+    /// the number of times the current position has occurred, including now
+    pub fn repetitions(&self) -> usize {
+        let cur = self.history.last().unwrap();
+        self.history.iter().filter(|&h| h == cur).count()
+    }
+
+    /// return the draw available in the current position, if any. automatic
+    /// draws take precedence over those that have to be claimed, and a
+    /// checkmate is never a draw
+    pub fn draw(&self) -> Option<Draw> {
+        let board = &self.board;
+        if board.is_checkmate() {
+            return None;
+        }
+        let repetitions = self.repetitions();
+        if board.is_stalemate() {
+            Some(Draw::Stalemate)
+        } else if board.is_insufficient_material() {
+            Some(Draw::InsufficientMaterial)
+        } else if repetitions >= 5 {
+            Some(Draw::FivefoldRepetition)
+        } else if board.half_move_clock >= 150 {
+            Some(Draw::SeventyFiveMoveRule)
+        } else if repetitions >= 3 {
+            Some(Draw::ThreefoldRepetition)
+        } else if board.half_move_clock >= 100 {
+            Some(Draw::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::{fen::square_name, piece::Piece, Board, LegalMove};
+
+impl Board {
+    /// return the standard algebraic notation for the legal move `mv`, with
+    /// only as much disambiguation as needed and a `+` or `#` suffix for
+    /// checks and checkmates
+    pub fn san(&self, mv: &LegalMove) -> String {
+        let LegalMove {
+            from,
+            to,
+            promotion,
+        } = *mv;
+        let Piece::Some { typ, .. } = self[from] else {
+            panic!("no piece to move from {from:?} on board:\n{self}");
+        };
+        let mut ret = String::new();
+        if self.is_castle(mv) {
+            ret.push_str(if to.1 > from.1 { "O-O" } else { "O-O-O" });
+        } else {
+            let from_name = square_name(from);
+            let (file, rank) = from_name.split_at(1);
+            let capture = self[to].is_some() || self.is_en_passant(mv);
+            if typ.is_pawn() {
+                if capture {
+                    ret.push_str(file);
+                }
+            } else {
+                ret.push(char::from(typ));
+                let others: Vec<_> = self
+                    .legal_moves()
+                    .into_iter()
+                    .filter(|m| {
+                        m.to == to
+                            && m.from != from
+                            && self[m.from] == self[from]
+                    })
+                    .collect();
+                if !others.is_empty() {
+                    if others.iter().all(|m| m.from.1 != from.1) {
+                        ret.push_str(file);
+                    } else if others.iter().all(|m| m.from.0 != from.0) {
+                        ret.push_str(rank);
+                    } else {
+                        ret.push_str(&from_name);
+                    }
+                }
+            }
+            if capture {
+                ret.push('x');
+            }
+            ret.push_str(&square_name(to));
+            if let Some(p) = promotion {
+                ret.push('=');
+                ret.push(char::from(p));
+            }
+        }
+
+        let mut after = self.clone();
+        after.play(mv);
+        if after.is_checkmate() {
+            ret.push('#');
+        } else if after.is_check() {
+            ret.push('+');
+        }
+        ret
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Display;
+
+use super::{
+    bitboard::{bit, index, Squares},
+    piece::Piece,
+    Board, LegalMove, PieceType,
+};
+
+/// the order in which pieces join an exchange, cheapest first
+const EXCHANGE_ORDER: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+/// a cheap explanation of a move that drops material, independent of any
+/// engine, like "loses a knight to Bxf7"
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterialLoss {
+    /// the piece the opponent wins
+    pub piece: PieceType,
+
+    /// the opponent's capture winning it, in SAN
+    pub reply: String,
+
+    /// the net material lost in pawns, after any exchanges
+    pub points: i32,
+}
+
+impl Display for MaterialLoss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.piece.name();
+        let article = if name.starts_with(['a', 'e', 'i', 'o', 'u']) {
+            "an"
+        } else {
+            "a"
+        };
+        write!(f, "loses {article} {name} to {}", self.reply)
+    }
+}
+
+impl Board {
+    /// the piece captured by `mv`, including en passant. castling is encoded
+    /// as the king landing on its own rook, so it captures nothing
+    fn captured(&self, mv: &LegalMove) -> Option<PieceType> {
+        let Piece::Some { color, .. } = self[mv.from] else {
+            return None;
+        };
+        match self[mv.to] {
+            Piece::Some { typ, color: c } if c != color => Some(typ),
+            Piece::Some { .. } => None,
+            Piece::None if self.is_en_passant(mv) => Some(PieceType::Pawn),
+            Piece::None => None,
+        }
+    }
+
+    /// the material `mv` wins immediately, counting a promotion as winning
+    /// the difference between the new piece and the pawn
+    fn immediate_gain(&self, mv: &LegalMove) -> i32 {
+        let captured = self.captured(mv).map_or(0, |t| t.value());
+        captured + mv.promotion.map_or(0, |p| p.value() - 1)
+    }
+
+    /// the static exchange evaluation of `mv`: the material the mover gains,
+    /// in pawns, if both sides keep capturing on the destination square with
+    /// their cheapest piece for as long as it pays. pins and checks are
+    /// ignored, and only the first move's promotion is counted
+    pub fn see(&self, mv: &LegalMove) -> i32 {
+        let Piece::Some { typ, color } = self[mv.from] else {
+            return 0;
+        };
+        if self.is_castle(mv) {
+            return 0;
+        }
+        let to = index(mv.to);
+        let mut occupied = self.occupied() & !bit(mv.from);
+        if self[mv.to].is_none() && self.captured(mv).is_some() {
+            occupied &= !bit((mv.from.0, mv.to.1));
+        }
+        // gains[d] is what the side making the dth capture wins if the
+        // exchange stopped there
+        let mut gains = vec![self.immediate_gain(mv)];
+        let mut on_square = mv.promotion.unwrap_or(typ).value();
+        let mut side = color.other();
+        loop {
+            let attackers = self.attackers_to(to, side, occupied);
+            let Some((typ, from)) = EXCHANGE_ORDER.iter().find_map(|&t| {
+                Squares(attackers & self.pieces[t as usize])
+                    .next()
+                    .map(|sq| (t, sq))
+            }) else {
+                break;
+            };
+            gains.push(on_square - gains.last().unwrap());
+            on_square = typ.value();
+            occupied &= !bit(from);
+            side = side.other();
+        }
+        // either side can stop capturing when continuing would lose more
+        for d in (1..gains.len()).rev() {
+            gains[d - 1] = -(-gains[d - 1]).max(gains[d]);
+        }
+        gains[0]
+    }
+
+    /// explain how `mv` drops material, if it does: after it, the opponent
+    /// has a capture that wins more by [Board::see] than `mv` itself won
+    pub fn material_loss(&self, mv: &LegalMove) -> Option<MaterialLoss> {
+        let gained = self.immediate_gain(mv);
+        let mut after = self.clone();
+        after.play(mv);
+        // prefer capturing with the cheapest piece when captures win the same
+        let (piece, reply, won) = after
+            .legal_moves()
+            .into_iter()
+            .filter_map(|reply| {
+                let piece = after.captured(&reply)?;
+                Some((piece, reply, after.see(&reply)))
+            })
+            .max_by_key(|&(_, reply, won)| {
+                let Piece::Some { typ, .. } = after[reply.from] else {
+                    unreachable!("legal moves start from a piece");
+                };
+                (won, -typ.value())
+            })?;
+        let points = won - gained;
+        (points > 0).then(|| MaterialLoss {
+            piece,
+            reply: after.san(&reply),
+            points,
+        })
+    }
+}
@@ -1,13 +1,13 @@
 use std::str::FromStr;
 
-use crate::pgn::{mov::Move, Pgn};
+use crate::pgn::{mov::Move, tests::fixture_games, GameError, Pgn};
 
 use super::*;
 
 #[test]
 fn fen_starting_position() {
     let board = Board::new();
-    let got = board.fen(0);
+    let got = board.fen();
     let want = String::from(
         "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
     );
@@ -17,8 +17,8 @@ fn fen_starting_position() {
 #[test]
 fn fen_e4() {
     let mut board = Board::new();
-    board.make_move(&"e4".parse().unwrap(), Color::White);
-    let got = board.fen(1);
+    board.make_move(&"e4".parse().unwrap()).unwrap();
+    let got = board.fen();
     let want = String::from(
         "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
     );
@@ -28,9 +28,9 @@ fn fen_e4() {
 #[test]
 fn fen_black_c5() {
     let mut board = Board::new();
-    board.make_move(&Move::from_str("e4").unwrap(), Color::White);
-    board.make_move(&Move::from_str("c5").unwrap(), Color::Black);
-    let got = board.fen(2);
+    board.make_move(&Move::from_str("e4").unwrap()).unwrap();
+    board.make_move(&Move::from_str("c5").unwrap()).unwrap();
+    let got = board.fen();
     let want = String::from(
         "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
     );
@@ -40,10 +40,10 @@ fn fen_black_c5() {
 #[test]
 fn fen_2_nf3() {
     let mut board = Board::new();
-    board.make_move(&Move::from_str("e4").unwrap(), Color::White);
-    board.make_move(&Move::from_str("c5").unwrap(), Color::Black);
-    board.make_move(&Move::from_str("Nf3").unwrap(), Color::White);
-    let got = board.fen(3);
+    board.make_move(&Move::from_str("e4").unwrap()).unwrap();
+    board.make_move(&Move::from_str("c5").unwrap()).unwrap();
+    board.make_move(&Move::from_str("Nf3").unwrap()).unwrap();
+    let got = board.fen();
     let want = String::from(
         "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
     );
@@ -77,11 +77,886 @@ fn samples() {
     let game = &Pgn::load("testfiles/sample.pgn").unwrap().games[0];
     for test in tests {
         let mut board = Board::new();
-        let mut to_move = [Color::White, Color::Black].into_iter().cycle();
-        for m in game.moves.iter().take(test.take) {
-            board.make_move(m, to_move.next().unwrap());
+        for m in game.main.moves.iter().take(test.take) {
+            board.make_move(m).unwrap();
         }
-        let got = board.fen(test.take);
+        let got = board.fen();
         assert_eq!(got, test.want, "take = {}, board =\n{board}", test.take);
     }
 }
+
+#[test]
+fn fen_promotion() {
+    let mut board = Board::new();
+    let moves = "h4 g5 hxg5 h6 gxh6 Bg7 hxg7 Nf6 g8=Q";
+    for m in moves.split_ascii_whitespace() {
+        board.make_move(&m.parse().unwrap()).unwrap();
+    }
+    let got = board.fen();
+    let want = "rnbqk1Qr/pppppp2/5n2/8/8/8/PPPPPPP1/RNBQKBNR b KQkq - 0 5";
+    assert_eq!(got, want);
+}
+
+#[test]
+fn en_passant() {
+    struct Test {
+        take: usize,
+        want: &'static str,
+    }
+    impl Test {
+        fn new(take: usize, want: &'static str) -> Self {
+            Self { take, want }
+        }
+    }
+    let tests = [
+        Test::new(
+            6,
+            "rnbqkbnr/ppp3pp/4p3/3pPp2/3P4/8/PPP2PPP/RNBQKBNR w KQkq f6 0 4",
+        ),
+        Test::new(
+            7,
+            "rnbqkbnr/ppp3pp/4pP2/3p4/3P4/8/PPP2PPP/RNBQKBNR b KQkq - 0 4",
+        ),
+        Test::new(
+            17,
+            "rnbq1rk1/pp4pp/3bpn2/3p4/1PpP4/5N2/P1P1BPPP/RNBQ1RK1 b - b3 0 9",
+        ),
+        Test::new(
+            18,
+            "rnbq1rk1/pp4pp/3bpn2/3p4/3P4/1p3N2/P1P1BPPP/RNBQ1RK1 w - - 0 10",
+        ),
+        Test::new(
+            20,
+            "r1bq1rk1/pp4pp/2nbpn2/3p4/3P4/1P3N2/2P1BPPP/RNBQ1RK1 w - - 1 11",
+        ),
+    ];
+
+    let game = &Pgn::load("testfiles/enpassant.pgn").unwrap().games[0];
+    for test in tests {
+        let mut board = Board::new();
+        for m in game.main.moves.iter().take(test.take) {
+            board.make_move(m).unwrap();
+        }
+        let got = board.fen();
+        assert_eq!(got, test.want, "take = {}, board =\n{board}", test.take);
+    }
+}
+
+/// build a board with only `pieces` on it and no castling rights
+fn with_pieces(pieces: &[(Coord, PieceType, Color)]) -> Board {
+    let mut board = Board::empty();
+    for &(sq, typ, color) in pieces {
+        board.put(sq.into(), Piece::Some { typ, color });
+    }
+    board
+}
+
+#[test]
+fn legal_moves_start() {
+    let mut board = Board::new();
+    assert_eq!(board.legal_moves().len(), 20);
+    board.make_move(&"e4".parse().unwrap()).unwrap();
+    assert_eq!(board.legal_moves().len(), 20);
+}
+
+#[test]
+fn pinned_piece_disambiguation() {
+    use Coord::*;
+    use PieceType::*;
+    // both knights can reach d2, but the one on b1 is pinned by the rook
+    let mut board = with_pieces(&[
+        (E1, King, Color::White),
+        (B1, Knight, Color::White),
+        (F1, Knight, Color::White),
+        (A1, Rook, Color::Black),
+        (E8, King, Color::Black),
+    ]);
+    board.make_move(&"Nd2".parse().unwrap()).unwrap();
+    assert_eq!(board.fen(), "4k3/8/8/8/8/8/3N4/rN2K3 b - - 1 1");
+}
+
+#[test]
+fn attackers() {
+    use Color::*;
+    use Coord::*;
+    let board = Board::new();
+    // the rook on a1 is blocked by its own pawn
+    assert_eq!(board.attackers(A3, White), vec![B1.into(), B2.into()]);
+    assert!(!board.is_attacked(A4, White));
+
+    let board: Board =
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4"
+            .parse()
+            .unwrap();
+    assert_eq!(board.attackers(F7, White), vec![C4.into(), H5.into()]);
+    assert_eq!(board.attackers(F7, Black), vec![E8.into()]);
+    // the pawn on e5 is attacked by the queen and defended by the knight
+    assert_eq!(board.attackers(E5, White), vec![H5.into()]);
+    assert_eq!(board.attackers(E5, Black), vec![C6.into()]);
+    assert!(board.is_attacked(H5, Black));
+    assert!(board.is_attacked(E6, White));
+    assert!(!board.is_attacked(G5, Black));
+}
+
+#[test]
+fn see() {
+    let tests = [
+        ("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5", 1),
+        // the pawn is defended by another pawn
+        ("4k3/8/2p5/3p4/8/4N3/8/4K3 w - - 0 1", "e3d5", -2),
+        // the rooks behind each other join the exchange in turn
+        ("3rk3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", -4),
+        ("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", 1),
+        // moving onto an attacked square without capturing
+        ("4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1", "d1d5", -9),
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5d6", 1),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q", 13),
+        // the king recaptures the new queen, unless the bishop behind the
+        // pawn defends it
+        ("4k3/8/8/8/8/8/3p4/2QK4 b - - 0 1", "d2c1q", 8),
+        ("4k3/8/8/8/5b2/8/3p4/2QK4 b - - 0 1", "d2c1q", 17),
+        ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", 0),
+    ];
+    for (fen, uci, want) in tests {
+        let board: Board = fen.parse().unwrap();
+        let mv = board.parse_uci(uci).unwrap();
+        assert_eq!(board.see(&mv), want, "{fen}: {uci}");
+    }
+}
+
+#[test]
+fn material_loss() {
+    let tests = [
+        (
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "f3d4",
+            Some("loses a knight to exd4"),
+        ),
+        (
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "f1c4",
+            None,
+        ),
+        (
+            "4k3/8/2p5/3p4/8/4N3/8/4K3 w - - 0 1",
+            "e3d5",
+            Some("loses a knight to cxd5"),
+        ),
+        // an even trade
+        ("4k3/8/2p5/3n4/8/4N3/8/4K3 w - - 0 1", "e3d5", None),
+        (
+            "4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1",
+            "d1d5",
+            Some("loses a queen to exd5"),
+        ),
+    ];
+    for (fen, uci, want) in tests {
+        let board: Board = fen.parse().unwrap();
+        let mv = board.parse_uci(uci).unwrap();
+        let got = board.material_loss(&mv).map(|loss| loss.to_string());
+        assert_eq!(got.as_deref(), want, "{fen}: {uci}");
+    }
+}
+
+#[test]
+fn material() {
+    let material = Board::new().material();
+    assert_eq!(material.white, material.black);
+    assert_eq!(material.white.count(PieceType::Pawn), 8);
+    assert_eq!(material.white.points(), 39);
+    assert_eq!(material.balance(), 0);
+    assert!(material.imbalances().is_empty());
+
+    use Imbalance::*;
+    let tests = [
+        // Black has lost a bishop
+        (
+            "r1bqk2r/pppp1ppp/2n2n2/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 5",
+            3,
+            vec![BishopPair(Color::White)],
+        ),
+        // White has given up a rook for a bishop
+        (
+            "1rb1k3/pppp4/8/8/8/8/PPPPP3/2B1KB2 w - - 0 1",
+            -1,
+            vec![BishopPair(Color::White), ExchangeDown(Color::White)],
+        ),
+        ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", 5, vec![]),
+    ];
+    for (fen, balance, want) in tests {
+        let material = fen.parse::<Board>().unwrap().material();
+        assert_eq!(material.balance(), balance, "{fen}");
+        assert_eq!(material.imbalances(), want, "{fen}");
+    }
+    assert_eq!(
+        ExchangeDown(Color::Black).to_string(),
+        "Black is the exchange down"
+    );
+}
+
+#[test]
+fn phase() {
+    let tests = [
+        (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            Phase::Opening,
+        ),
+        // both sides have brought out their queens and connected their rooks
+        (
+            "r4rk1/ppp1qppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPP1QPPP/R4RK1 w - - 0 9",
+            Phase::Middlegame,
+        ),
+        (
+            "r4rk1/pp3ppp/8/8/8/8/PP3PPP/R4RK1 w - - 0 20",
+            Phase::Endgame,
+        ),
+        ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", Phase::Endgame),
+    ];
+    for (fen, want) in tests {
+        let board: Board = fen.parse().unwrap();
+        assert_eq!(board.phase(), want, "{fen}");
+    }
+}
+
+#[test]
+fn flipped() {
+    let mut board = Board::new();
+    board.make_move(&Move::from_str("e4").unwrap()).unwrap();
+    let want = "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1";
+    assert_eq!(board.flipped().fen(), want);
+    assert_eq!(board.flipped().flipped(), board);
+
+    // Kiwipete, a Chess960 position, and one with en passant and promotions
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        "4k3/1P6/8/3pP3/8/8/6p1/4K3 w - d6 0 2",
+    ] {
+        let board: Board = fen.parse().unwrap();
+        let flipped = board.flipped();
+        assert_eq!(flipped.hash(), flipped.compute_hash());
+        let mut want: Vec<_> =
+            board.legal_moves().iter().map(LegalMove::flipped).collect();
+        let mut got = flipped.legal_moves();
+        want.sort_by_key(|mv| (mv.from, mv.to, mv.promotion));
+        got.sort_by_key(|mv| (mv.from, mv.to, mv.promotion));
+        assert_eq!(got, want, "{fen}");
+        assert_eq!(flipped.perft(3), board.perft(3), "{fen}");
+    }
+}
+
+#[test]
+fn mirrored() {
+    let board: Board = "4k3/1P6/8/3pP3/8/8/6p1/4K3 w - d6 0 2".parse().unwrap();
+    let mirrored = board.mirrored();
+    assert_eq!(mirrored.fen(), "3k4/6P1/8/3Pp3/8/8/1p6/3K4 w - e6 0 2");
+    assert_eq!(mirrored.mirrored(), board);
+    let mut want: Vec<_> = board
+        .legal_moves()
+        .iter()
+        .map(LegalMove::mirrored)
+        .collect();
+    let mut got = mirrored.legal_moves();
+    want.sort_by_key(|mv| (mv.from, mv.to, mv.promotion));
+    got.sort_by_key(|mv| (mv.from, mv.to, mv.promotion));
+    assert_eq!(got, want);
+
+    // the rights move to the other rooks, which makes the castling Chess960
+    let board: Board = "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1".parse().unwrap();
+    let mirrored = board.mirrored();
+    assert!(mirrored.chess960());
+    assert_eq!(mirrored.fen(), "r2k3r/8/8/8/8/8/8/R2K3R w Qk - 0 1");
+}
+
+#[test]
+fn move_transforms() {
+    for (f, game) in fixture_games() {
+        let mut board = game.starting_position().unwrap();
+        let sans = game.flipped().unwrap().main.sans;
+        for (m, san) in game.main.moves.iter().zip(sans) {
+            let mv = board.resolve(m).unwrap();
+            let flipped = board.flipped().resolve(&m.flipped());
+            assert_eq!(flipped, Ok(mv.flipped()), "{f}: {m}");
+            assert_eq!(board.flipped().san(&mv.flipped()), san, "{f}: {m}");
+            let mirrored = board.mirrored().resolve(&m.mirrored());
+            assert_eq!(mirrored, Ok(mv.mirrored()), "{f}: {m}");
+            board.play(&mv);
+        }
+        // Black moves first in the flipped game, so the move numbers
+        // don't line up, but the positions do
+        let flipped = game.flipped().unwrap().replay().unwrap();
+        assert_eq!(flipped.hash(), board.flipped().hash(), "{f}");
+        match game.mirrored() {
+            Ok(mirrored) => {
+                let mirrored = mirrored.replay().unwrap();
+                assert_eq!(mirrored.fen(), board.mirrored().fen(), "{f}");
+            }
+            Err(e) => assert_eq!(e, GameError::Castling, "{f}"),
+        }
+    }
+}
+
+#[test]
+fn castle_through_check() {
+    use Coord::*;
+    use PieceType::*;
+    let mut board = with_pieces(&[
+        (E1, King, Color::White),
+        (H1, Rook, Color::White),
+        (F8, Rook, Color::Black),
+        (E8, King, Color::Black),
+    ]);
+    board.castling[0] = Some(7);
+    let castle = LegalMove::new(E1.into(), H1.into());
+    assert!(!board.legal_moves().contains(&castle));
+    assert!(board.make_move(&Move::KingCastle).is_err());
+}
+
+#[test]
+fn illegal_move() {
+    let mut board = Board::new();
+    let got = board.make_move(&"Nd2".parse().unwrap()).unwrap_err();
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(
+        got,
+        IllegalMove {
+            ply: 1,
+            san: "Nd2".to_owned(),
+            fen: fen.to_owned(),
+        }
+    );
+    assert_eq!(
+        got.to_string(),
+        format!("illegal move 1. Nd2 in position {fen}")
+    );
+    // the board is left as it was
+    assert_eq!(board, Board::new());
+
+    // queens on c3, e3, and c5 can all reach d4
+    let mut board: Board =
+        "k7/8/8/2Q5/8/2Q1Q3/8/4K3 b - - 0 30".parse().unwrap();
+    board.make_move(&"Kb7".parse().unwrap()).unwrap();
+    let got = board.make_move(&"Qd4".parse().unwrap()).unwrap_err();
+    assert_eq!(got.ply, 61);
+    assert!(got.to_string().starts_with("illegal move 31. Qd4 "));
+}
+
+#[test]
+fn fen_round_trip() {
+    for (f, game) in fixture_games() {
+        let mut board = game.starting_position().unwrap();
+        for m in &game.main.moves {
+            board.make_move(m).unwrap();
+            let fen = board.fen();
+            let mut got = Board::from_fen(&fen).unwrap();
+            // once the castling rights are gone, the FEN can't tell a
+            // Chess960 game from a standard one
+            if board.castling == [None; 4] {
+                got.chess960 = board.chess960;
+            }
+            assert_eq!(got, board, "{f}: {fen}");
+            assert_eq!(got.fen(), fen);
+        }
+    }
+}
+
+#[test]
+fn fen_from_str() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(fen.parse::<Board>().unwrap(), Board::new());
+}
+
+#[test]
+fn fen_errors() {
+    use FenError::*;
+    let tests = [
+        ("8/8/8/8/8/8/8/8 w - -", FieldCount(4)),
+        (
+            "8/8/8/8/8/8/8 w - - 0 1",
+            Placement("8/8/8/8/8/8/8".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+            Placement("4k3/8/8/8/8/8/8/4K4".to_owned()),
+        ),
+        ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", Piece('X')),
+        ("4k3/8/8/8/8/8/8/8 w - - 0 1", Kings),
+        ("4k3/8/8/8/8/8/8/P3K3 w - - 0 1", PawnOnBackRank),
+        ("4k3/8/8/8/8/8/8/4K3 x - - 0 1", SideToMove("x".to_owned())),
+        ("4k3/8/8/8/8/8/8/4K3 w KK - 0 1", Castling("KK".to_owned())),
+        ("4k3/8/8/8/8/8/8/4K3 w K - 0 1", Castling("K".to_owned())),
+        (
+            "r3k3/8/8/8/8/8/8/4K2R w KQkq - 0 1",
+            Castling("KQkq".to_owned()),
+        ),
+        ("4k3/8/8/8/8/8/8/4K3 w - e3 0 1", EnPassant("e3".to_owned())),
+        ("4k3/8/8/8/8/8/8/4K3 w - e6 0 1", EnPassant("e6".to_owned())),
+        (
+            "4k3/8/8/3PN3/8/8/8/4K3 w - e6 0 1",
+            EnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/8/4n3/3Pp3/8/8/8/4K3 w - e6 0 1",
+            EnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/4n3/8/3Pp3/8/8/8/4K3 w - e6 0 1",
+            EnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/4p3/8/8/4K3 b - e3 0 1",
+            EnPassant("e3".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            HalfMoveClock("x".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+            FullMoveNumber("0".to_owned()),
+        ),
+    ];
+    for (fen, want) in tests {
+        assert_eq!(Board::from_fen(fen).unwrap_err(), want, "{fen}");
+    }
+}
+
+#[test]
+fn check_mate_stale() {
+    let tests = [
+        (Board::new().fen(), false, false, false),
+        // fool's mate
+        (
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"
+                .to_owned(),
+            true,
+            true,
+            false,
+        ),
+        // the king can escape to f1
+        (
+            "4k3/8/8/8/8/8/8/4K2r w - - 0 1".to_owned(),
+            true,
+            false,
+            false,
+        ),
+        (
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".to_owned(),
+            false,
+            false,
+            true,
+        ),
+    ];
+    for (fen, check, mate, stale) in tests {
+        let board = Board::from_fen(&fen).unwrap();
+        assert_eq!(board.is_check(), check, "{fen}");
+        assert_eq!(board.is_checkmate(), mate, "{fen}");
+        assert_eq!(board.is_stalemate(), stale, "{fen}");
+    }
+}
+
+#[test]
+fn repetition() {
+    let mut replay = Replay::new(Board::new());
+    let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+    let mut draws = Vec::new();
+    for m in shuffle.iter().cycle().take(16) {
+        replay.make_move(&Move::from_str(m).unwrap()).unwrap();
+        draws.push(replay.draw());
+    }
+    use Draw::*;
+    assert_eq!(replay.repetitions(), 5);
+    assert_eq!(draws[3], None);
+    assert_eq!(draws[7], Some(ThreefoldRepetition));
+    assert_eq!(draws[6], None);
+    assert_eq!(draws[15], Some(FivefoldRepetition));
+}
+
+#[test]
+fn repetition_en_passant() {
+    // after 1. e4 the e3 target can't be captured, so returning to the same
+    // position later still counts as a repetition. after 1... d4?? 2. c4 it
+    // can
+    let board: Board = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".parse().unwrap();
+    let mut replay = Replay::new(board);
+    for m in ["e4", "Kd7", "Kd2", "Ke8", "Ke1", "Kd7", "Kd2", "Ke8", "Ke1"] {
+        replay.make_move(&Move::from_str(m).unwrap()).unwrap();
+    }
+    assert_eq!(replay.repetitions(), 3);
+
+    let board: Board = "4k3/8/8/8/3p4/8/2P5/4K3 w - - 0 1".parse().unwrap();
+    let mut replay = Replay::new(board);
+    for m in ["c4", "Kd7", "Kd2", "Ke8", "Ke1"] {
+        replay.make_move(&Move::from_str(m).unwrap()).unwrap();
+    }
+    assert_eq!(replay.repetitions(), 1);
+}
+
+#[test]
+fn move_rules() {
+    use Draw::*;
+    for (clock, want) in [
+        (98, None),
+        (99, Some(FiftyMoveRule)),
+        (149, Some(SeventyFiveMoveRule)),
+    ] {
+        let fen = format!("4k3/8/8/8/8/8/4P3/4K3 w - - {clock} 80");
+        let mut replay = Replay::new(fen.parse().unwrap());
+        replay.make_move(&Move::from_str("Kd2").unwrap()).unwrap();
+        assert_eq!(replay.board().half_move_clock(), clock + 1);
+        assert_eq!(replay.draw(), want, "{fen}");
+    }
+}
+
+#[test]
+fn insufficient_material() {
+    let tests = [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/4KB2 w - - 0 1", true),
+        // bishops on the same color
+        ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        // bishops on opposite colors
+        ("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+        ("4k1n1/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/4K2R w - - 0 1", false),
+    ];
+    for (fen, want) in tests {
+        let board: Board = fen.parse().unwrap();
+        assert_eq!(board.is_insufficient_material(), want, "{fen}");
+    }
+}
+
+#[test]
+fn hash_incremental() {
+    for (f, game) in fixture_games() {
+        let mut board = game.starting_position().unwrap();
+        assert_eq!(board.hash(), board.compute_hash());
+        for m in &game.main.moves {
+            board.make_move(m).unwrap();
+            assert_eq!(board.hash(), board.compute_hash(), "{f}: {m}");
+            let parsed = Board::from_fen(&board.fen()).unwrap();
+            assert_eq!(board.hash(), parsed.hash(), "{f}: {m}");
+        }
+    }
+}
+
+#[test]
+fn hash_transposition() {
+    let play = |moves: &[&str]| {
+        let mut board = Board::new();
+        for m in moves {
+            board.make_move(&Move::from_str(m).unwrap()).unwrap();
+        }
+        board
+    };
+    let a = play(&["Nf3", "Nf6", "Nc3"]);
+    let b = play(&["Nc3", "Nf6", "Nf3"]);
+    assert_eq!(a.hash(), b.hash());
+
+    // the same position reached by shuffling knights
+    let c = play(&["Nf3", "Nf6", "Nc3", "Nc6", "Nb1", "Nb8"]);
+    assert_eq!(c.hash(), play(&["Nf3", "Nf6"]).hash());
+
+    // same pieces, different side to move
+    let d: Board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"
+        .parse()
+        .unwrap();
+    assert_ne!(d.hash(), Board::new().hash());
+
+    // losing castling rights changes the hash
+    let e = play(&["Nf3", "Nf6", "Rg1", "Ng8", "Rh1", "Nf6"]);
+    assert_ne!(e.hash(), play(&["Nf3", "Nf6"]).hash());
+
+    // an en passant target only counts when it can be captured
+    let f = play(&["e4"]);
+    let g: Board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        .parse()
+        .unwrap();
+    assert_eq!(f.hash(), g.hash());
+    let h = play(&["e4", "d5", "e5", "f5"]);
+    let i: Board =
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3"
+            .parse()
+            .unwrap();
+    assert_ne!(h.hash(), i.hash());
+}
+
+/// the standard perft positions and their node counts at increasing depths,
+/// from https://www.chessprogramming.org/Perft_Results
+#[test]
+fn perft() {
+    let tests = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902, 197281][..],
+        ),
+        // Kiwipete
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - \
+             0 1",
+            &[48, 2039, 97862],
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238]),
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467],
+        ),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        ),
+        (
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - \
+             - 0 10",
+            &[46, 2079, 89890],
+        ),
+    ];
+    for (fen, counts) in tests {
+        let board = Board::from_fen(fen).unwrap();
+        for (depth, &want) in counts.iter().enumerate() {
+            let depth = depth + 1;
+            assert_eq!(board.perft(depth), want, "{fen} at depth {depth}");
+        }
+    }
+}
+
+#[test]
+fn perft_chess960() {
+    let tests = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12189],
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            &[21, 807, 18002],
+        ),
+        (
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10471],
+        ),
+        (
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            &[22, 593, 13440],
+        ),
+        (
+            "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 \
+             9",
+            &[28, 1120, 31058],
+        ),
+    ];
+    for (fen, counts) in tests {
+        let board = Board::from_fen(fen).unwrap();
+        assert!(board.chess960());
+        for (depth, &want) in counts.iter().enumerate() {
+            let depth = depth + 1;
+            assert_eq!(board.perft(depth), want, "{fen} at depth {depth}");
+        }
+    }
+}
+
+#[test]
+fn castling_fields() {
+    let tests = [
+        // standard chess
+        ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "KQkq", false),
+        // Shredder-FEN files are written as KQkq when that's unambiguous
+        ("rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1", "KQkq", true),
+        // a second rook outside the castling one needs its file
+        ("4k3/8/8/8/8/8/8/1RK3RR w G - 0 1", "G", true),
+        ("4k3/8/8/8/8/8/8/1RK3RR w GB - 0 1", "GQ", true),
+    ];
+    for (fen, want, chess960) in tests {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.fen_castle_field(), want, "{fen}");
+        assert_eq!(board.chess960(), chess960, "{fen}");
+        let again = Board::from_fen(&board.fen()).unwrap();
+        assert_eq!(again.castling, board.castling, "{fen}");
+    }
+    // there's no rook on the king's side of the king on h1
+    assert!(Board::from_fen("4k3/8/8/8/8/8/8/R6K w H - 0 1").is_err());
+}
+
+#[test]
+fn castle_chess960() {
+    let tests = [
+        // the king starts on b1 and the rook on a1, so they swap sides
+        ("1k6/8/8/8/8/8/8/RK6 w A - 0 1", "b1a1", "O-O-O", "2KR4"),
+        // the king jumps over its rook from g1 to c1
+        ("6k1/8/8/8/8/8/8/5RK1 w F - 0 1", "g1f1", "O-O-O", "2KR4"),
+        // the king starts on f1, next to its destination
+        ("1k6/8/8/8/8/8/8/4RK1R w H - 0 1", "f1h1", "O-O", "4RRK1"),
+        // the king already stands on g1, so only the rook moves
+        ("1k6/8/8/8/8/8/8/6KR w H - 0 1", "g1h1", "O-O", "5RK1"),
+    ];
+    for (fen, uci, san, rank) in tests {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mv = board.parse_uci(uci).unwrap();
+        assert_eq!(board.san(&mv), san, "{fen}");
+        let undo = board.play(&mv);
+        let placement = board.fen();
+        assert!(
+            placement.split(' ').next().unwrap().ends_with(rank),
+            "{fen}"
+        );
+        assert_eq!(board.hash(), board.compute_hash());
+        board.unmake_move(&undo);
+        assert_eq!(board, Board::from_fen(fen).unwrap());
+    }
+}
+
+#[test]
+fn divide() {
+    let board = Board::new();
+    let got = board.divide(2);
+    assert_eq!(got.len(), 20);
+    assert!(got.iter().all(|&(_, n)| n == 20));
+    let moves: Vec<_> = got.iter().map(|(mv, _)| mv.to_string()).collect();
+    assert!(moves.contains(&"e2e4".to_owned()));
+    assert!(moves.contains(&"g1f3".to_owned()));
+
+    let board: Board = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+    let moves: Vec<_> = board
+        .divide(1)
+        .iter()
+        .map(|(mv, _)| mv.to_string())
+        .collect();
+    assert!(moves.contains(&"b7b8q".to_owned()));
+    assert!(moves.contains(&"b7b8n".to_owned()));
+}
+
+#[test]
+fn unmake_move() {
+    for (f, game) in fixture_games() {
+        let mut board = game.starting_position().unwrap();
+        let mut history = Vec::new();
+        for m in &game.main.moves {
+            let before = board.clone();
+            let undo = board.make_move(m).unwrap();
+            history.push((before, undo));
+        }
+        while let Some((before, undo)) = history.pop() {
+            board.unmake_move(&undo);
+            assert_eq!(board, before, "{f}: {}", undo.mv());
+            assert_eq!(board.fen(), before.fen());
+        }
+    }
+}
+
+#[test]
+fn unmake_special_moves() {
+    // castling, promotion with capture, and en passant
+    let tests = [
+        ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"),
+        ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n"),
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5d6"),
+    ];
+    for (fen, want) in tests {
+        let mut board: Board = fen.parse().unwrap();
+        let mv = board
+            .legal_moves()
+            .into_iter()
+            .find(|mv| board.uci_move(mv) == want)
+            .unwrap();
+        let undo = board.play(&mv);
+        assert_ne!(board.fen(), fen);
+        board.unmake_move(&undo);
+        assert_eq!(board, fen.parse().unwrap(), "{want}");
+    }
+}
+
+#[test]
+fn san_round_trip() {
+    use crate::pgn::mov::Indicator;
+    for (f, game) in fixture_games() {
+        let mut board = game.starting_position().unwrap();
+        for (m, &check) in game.main.moves.iter().zip(&game.main.checks) {
+            let mv = board.resolve(m).unwrap();
+            let san = board.san(&mv);
+            assert_eq!(Move::from_str(&san).unwrap(), *m, "{f}: {san}");
+            assert_eq!(Indicator::from_san(&san), check, "{f}: {san}");
+            board.play(&mv);
+        }
+    }
+}
+
+#[test]
+fn san() {
+    let tests = [
+        // knights on b1 and f3 can both reach d2
+        ("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "b1d2", "Nbd2"),
+        // rooks on a1 and a5 share a file
+        ("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3", "R1a3"),
+        // queens on c3, e3, and c5 need both the file and rank for the one
+        // on c3
+        ("k7/8/8/2Q5/8/2Q1Q3/8/4K3 w - - 0 1", "c3d4", "Qc3d4"),
+        // the knight on c3 is pinned, so Ne2 needs no disambiguation
+        ("4k3/8/8/b7/8/2N5/8/4KN2 w - - 0 1", "f1e3", "Ne3"),
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5d6", "exd6"),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q", "axb8=Q+"),
+        ("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8r", "a8=R+"),
+        ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "O-O"),
+        ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "O-O-O"),
+        (
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+            "d8h4",
+            "Qh4#",
+        ),
+    ];
+    for (fen, uci, want) in tests {
+        let board: Board = fen.parse().unwrap();
+        let mv = board
+            .legal_moves()
+            .into_iter()
+            .find(|mv| board.uci_move(mv) == uci)
+            .unwrap();
+        assert_eq!(board.san(&mv), want, "{fen}");
+    }
+}
+
+#[test]
+fn uci_round_trip() {
+    for (f, game) in fixture_games() {
+        let mut board = game.starting_position().unwrap();
+        for m in &game.main.moves {
+            let uci = board.uci(m).unwrap();
+            assert_eq!(board.move_from_uci(&uci).unwrap(), *m, "{f}: {uci}");
+            board.make_move(m).unwrap();
+        }
+    }
+}
+
+#[test]
+fn uci() {
+    let board: Board = "r3k3/1P6/8/8/8/8/8/4K2R w K - 0 1".parse().unwrap();
+    for (s, want) in [("b7a8q", "bxa8=Q+"), ("b7b8n", "b8=N"), ("e1g1", "O-O")]
+    {
+        let mv = board.parse_uci(s).unwrap();
+        assert_eq!(board.uci_move(&mv), s);
+        assert_eq!(board.san(&mv), want);
+    }
+    // Chess960 engines expect castling as the king capturing its rook
+    let mut chess960 = board.clone();
+    chess960.set_chess960(true);
+    let castle = chess960.parse_uci("e1h1").unwrap();
+    assert_eq!(castle, board.parse_uci("e1g1").unwrap());
+    assert!(chess960.parse_uci("e1g1").is_err());
+    use UciError::*;
+    for (s, want) in [
+        ("b7b8", Illegal("b7b8".to_owned())),
+        ("e1e3", Illegal("e1e3".to_owned())),
+        ("b7b8k", Syntax("b7b8k".to_owned())),
+        ("e1", Syntax("e1".to_owned())),
+        ("i1e1", Syntax("i1e1".to_owned())),
+    ] {
+        assert_eq!(board.parse_uci(s).unwrap_err(), want);
+    }
+
+    let pv = ["e2e4", "e7e5", "g1f3"].map(String::from);
+    let got = Board::new().uci_line_to_san(&pv).unwrap();
+    assert_eq!(got, ["e4", "e5", "Nf3"]);
+}
@@ -0,0 +1,91 @@
+use super::{piece::Piece, Board, LegalMove, Square};
+
+/// the square on the same file, counted from the other side of the board
+fn flip_square((rank, file): Square) -> Square {
+    (7 - rank, file)
+}
+
+/// the square on the same rank, counted from the other edge of the board
+fn mirror_square((rank, file): Square) -> Square {
+    (rank, 7 - file)
+}
+
+impl Board {
+    /// the same position with the board turned upside down and the colors
+    /// swapped, so that White's pieces stand where Black's did and the other
+    /// side is to move. the evaluation of the result should be the negation
+    /// of the evaluation of `self`
+    pub fn flipped(&self) -> Self {
+        let mut ret = self.transformed(flip_square, true);
+        ret.to_move = self.to_move.other();
+        let [wk, wq, bk, bq] = self.castling;
+        ret.castling = [bk, bq, wk, wq];
+        ret.chess960 = self.chess960;
+        ret.en_passant_target = self.en_passant_target.map(flip_square);
+        ret.hash = ret.compute_hash();
+        ret
+    }
+
+    /// the same position reflected across the line between the d- and
+    /// e-files. a kingside castling right becomes a queenside one, since its
+    /// rook ends up on the other side of the king, but castling itself isn't
+    /// symmetric, so the result is treated as a Chess960 position whenever it
+    /// keeps any castling rights
+    pub fn mirrored(&self) -> Self {
+        let mut ret = self.transformed(mirror_square, false);
+        ret.to_move = self.to_move;
+        let [wk, wq, bk, bq] = self.castling.map(|r| r.map(|file| 7 - file));
+        ret.castling = [wq, wk, bq, bk];
+        ret.chess960 = self.chess960 || !ret.has_standard_castling();
+        ret.en_passant_target = self.en_passant_target.map(mirror_square);
+        ret.hash = ret.compute_hash();
+        ret
+    }
+
+    /// move each piece of `self` to the square `f` gives for it on an empty
+    /// board, swapping its color if `swap` is true, and copy the move clocks.
+    /// the caller is responsible for the rest of the state and the hash
+    fn transformed(&self, f: fn(Square) -> Square, swap: bool) -> Self {
+        let mut ret = Self {
+            half_move_clock: self.half_move_clock,
+            full_move: self.full_move,
+            ..Self::empty()
+        };
+        for rank in 0..8 {
+            for file in 0..8 {
+                let piece = match self[(rank, file)] {
+                    Piece::Some { typ, color } if swap => Piece::Some {
+                        typ,
+                        color: color.other(),
+                    },
+                    p => p,
+                };
+                ret.put(f((rank, file)), piece);
+            }
+        }
+        ret
+    }
+}
+
+impl LegalMove {
+    /// the corresponding move on the [Board::flipped] board
+    pub fn flipped(&self) -> Self {
+        Self {
+            from: flip_square(self.from),
+            to: flip_square(self.to),
+            promotion: self.promotion,
+        }
+    }
+
+    /// the corresponding move on the [Board::mirrored] board. castling moves
+    /// keep their encoding as the king capturing its own rook, so they're
+    /// still castling moves there, although the king and rook end up on
+    /// different squares than in the reflection of the original castling
+    pub fn mirrored(&self) -> Self {
+        Self {
+            from: mirror_square(self.from),
+            to: mirror_square(self.to),
+            promotion: self.promotion,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{
+    castling::castled_files, fen::parse_square, Board, IllegalMove, LegalMove,
+    PieceType,
+};
+use crate::pgn::mov::Move;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UciError {
+    /// the move was not in long algebraic notation like `e2e4` or `e7e8q`
+    Syntax(String),
+
+    /// the move was well-formed but not legal in the position
+    Illegal(String),
+}
+
+impl Display for UciError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciError::Syntax(s) => write!(f, "invalid UCI move `{s}`"),
+            UciError::Illegal(s) => write!(f, "illegal UCI move `{s}`"),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
+
+/// parse a move in the long algebraic notation used by UCI engines. this only
+/// checks the syntax, see [Board::parse_uci] for checking the move is legal
+impl FromStr for LegalMove {
+    type Err = UciError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || UciError::Syntax(s.to_owned());
+        if !s.is_ascii() || !(4..=5).contains(&s.len()) {
+            return Err(err());
+        }
+        let from = parse_square(&s[0..2]).ok_or_else(err)?;
+        let to = parse_square(&s[2..4]).ok_or_else(err)?;
+        let promotion = match &s[4..] {
+            "" => None,
+            "q" => Some(PieceType::Queen),
+            "r" => Some(PieceType::Rook),
+            "b" => Some(PieceType::Bishop),
+            "n" => Some(PieceType::Knight),
+            _ => return Err(err()),
+        };
+        Ok(Self {
+            from,
+            to,
+            promotion,
+        })
+    }
+}
+
+impl Board {
+    /// parse the UCI move `s`, checking that it's legal in this position.
+    /// castling is accepted in the form given by [Board::uci_move]
+    pub fn parse_uci(&self, s: &str) -> Result<LegalMove, UciError> {
+        let want = s.parse::<LegalMove>()?.to_string();
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| self.uci_move(mv) == want)
+            .ok_or_else(|| UciError::Illegal(s.to_owned()))
+    }
+
+    /// write the legal move `mv` in UCI notation. castling is written as the
+    /// king moving two squares, like `e1g1`, except in Chess960, where it's
+    /// the king capturing its own rook
+    pub fn uci_move(&self, mv: &LegalMove) -> String {
+        if self.is_castle(mv) && !self.chess960 {
+            let (king_to, _) = castled_files(mv.to.1 > mv.from.1);
+            return LegalMove::new(mv.from, (mv.from.0, king_to)).to_string();
+        }
+        mv.to_string()
+    }
+
+    /// convert the SAN move `m` into UCI notation
+    pub fn uci(&self, m: &Move) -> Result<String, IllegalMove> {
+        Ok(self.uci_move(&self.resolve(m)?))
+    }
+
+    /// convert the UCI move `s` into a SAN [Move]
+    pub fn move_from_uci(&self, s: &str) -> Result<Move, UciError> {
+        let mv = self.parse_uci(s)?;
+        Ok(self.san(&mv).parse().expect("generated SAN should parse"))
+    }
+
+    /// convert a line of UCI moves starting from this position, like an
+    /// engine's principal variation, to SAN
+    pub fn uci_line_to_san(
+        &self,
+        line: &[String],
+    ) -> Result<Vec<String>, UciError> {
+        let mut board = self.clone();
+        let mut ret = Vec::with_capacity(line.len());
+        for s in line {
+            let mv = board.parse_uci(s)?;
+            ret.push(board.san(&mv));
+            board.play(&mv);
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,77 @@
+use super::{piece::Piece, Board, LegalMove, PieceType, Square};
+
+/// the information needed to take back a move with [Board::unmake_move], as
+/// returned by [Board::play]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Undo {
+    /// the move that was played
+    pub(crate) mv: LegalMove,
+
+    /// the piece captured by the move and the square it stood on, which is
+    /// not the destination of an en passant capture
+    pub(crate) captured: Option<(Square, Piece)>,
+
+    pub(crate) half_move_clock: usize,
+    pub(crate) en_passant_target: Option<Square>,
+
+    /// the castling rights, in the order of [Board::castling]
+    pub(crate) castling: [Option<usize>; 4],
+
+    /// whether the move castled, since the king and rook no longer stand on
+    /// its squares
+    pub(crate) castled: bool,
+
+    pub(crate) hash: u64,
+}
+
+impl Undo {
+    /// the move that was played
+    pub fn mv(&self) -> LegalMove {
+        self.mv
+    }
+}
+
+impl Board {
+    /// the castling rights for White kingside, White queenside, Black
+    /// kingside, and Black queenside, in that order
+    pub(crate) fn castling_rights(&self) -> [bool; 4] {
+        self.castling.map(|file| file.is_some())
+    }
+
+    /// take back the move recorded in `undo`, which must be the last move
+    /// played on `self`
+    pub fn unmake_move(&mut self, undo: &Undo) {
+        let LegalMove {
+            from,
+            to,
+            promotion,
+        } = undo.mv;
+        let color = self.to_move.other();
+        if undo.castled {
+            // the king and rook no longer stand on `from` and `to`
+            self.uncastle(&undo.mv);
+        } else {
+            let piece = if promotion.is_some() {
+                Piece::Some {
+                    typ: PieceType::Pawn,
+                    color,
+                }
+            } else {
+                self[to]
+            };
+            self.put(to, Piece::None);
+            self.put(from, piece);
+        }
+        if let Some((sq, captured)) = undo.captured {
+            self.put(sq, captured);
+        }
+        self.castling = undo.castling;
+        self.half_move_clock = undo.half_move_clock;
+        self.en_passant_target = undo.en_passant_target;
+        if color.is_black() {
+            self.full_move -= 1;
+        }
+        self.to_move = color;
+        self.hash = undo.hash;
+    }
+}
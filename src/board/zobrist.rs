@@ -0,0 +1,143 @@
+use super::{piece::Piece, Board, Color, PieceType, Square};
+
+/// the random keys for Zobrist hashing. the hash of a position is the XOR of
+/// the keys of the features present, so it can be updated incrementally as
+/// pieces move
+struct Keys {
+    /// indexed by [piece_index] and then by `8 * rank + file`
+    pieces: [[u64; 64]; 12],
+
+    /// white kingside, white queenside, black kingside, black queenside
+    castling: [u64; 4],
+
+    /// indexed by the file of the en passant target
+    en_passant: [u64; 8],
+
+    black_to_move: u64,
+}
+
+/// the splitmix64 generator, which is plenty random for hash keys and can run
+/// at compile time
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl Keys {
+    const fn new() -> Self {
+        let mut state = 0x636865636b706c75; // "checkplu"
+        let mut pieces = [[0; 64]; 12];
+        let mut p = 0;
+        while p < 12 {
+            let mut sq = 0;
+            while sq < 64 {
+                pieces[p][sq] = splitmix64(&mut state);
+                sq += 1;
+            }
+            p += 1;
+        }
+        let mut castling = [0; 4];
+        let mut i = 0;
+        while i < 4 {
+            castling[i] = splitmix64(&mut state);
+            i += 1;
+        }
+        let mut en_passant = [0; 8];
+        let mut i = 0;
+        while i < 8 {
+            en_passant[i] = splitmix64(&mut state);
+            i += 1;
+        }
+        let black_to_move = splitmix64(&mut state);
+        Self {
+            pieces,
+            castling,
+            en_passant,
+            black_to_move,
+        }
+    }
+}
+
+static KEYS: Keys = Keys::new();
+
+/// the row of [Keys::pieces] for a piece of type `typ` and color `color`
+fn piece_index(typ: PieceType, color: Color) -> usize {
+    let c = match color {
+        Color::White => 0,
+        Color::Black => 6,
+    };
+    c + typ as usize
+}
+
+/// the key for `piece` standing on `(rank, file)`, or 0 for an empty square
+pub(crate) fn piece_key((rank, file): Square, piece: Piece) -> u64 {
+    match piece {
+        Piece::Some { typ, color } => {
+            KEYS.pieces[piece_index(typ, color)][8 * rank + file]
+        }
+        Piece::None => 0,
+    }
+}
+
+impl Board {
+    /// the 64-bit Zobrist key of the position, covering the piece placement,
+    /// castling rights, en passant file, and side to move. the en passant file
+    /// is only included when a pawn could capture there, so positions that
+    /// only differ by an unusable target hash the same. the keys are our own,
+    /// so these hashes aren't compatible with Polyglot opening books
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// compute the hash of `self` from scratch, rather than incrementally
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let mut ret = self.state_key();
+        for rank in 0..8 {
+            for file in 0..8 {
+                ret ^= piece_key((rank, file), self[(rank, file)]);
+            }
+        }
+        ret
+    }
+
+    /// the combined key for everything other than the pieces, which is XORed
+    /// out before a move and back in after it
+    pub(crate) fn state_key(&self) -> u64 {
+        let mut ret = 0;
+        for (right, key) in
+            self.castling_rights().into_iter().zip(KEYS.castling)
+        {
+            if right {
+                ret ^= key;
+            }
+        }
+        if let Some(target) = self.en_passant_target
+            && self.can_capture_en_passant(target)
+        {
+            ret ^= KEYS.en_passant[target.1];
+        }
+        if self.to_move.is_black() {
+            ret ^= KEYS.black_to_move;
+        }
+        ret
+    }
+
+    /// report whether a pawn of the side to move stands beside the pawn that
+    /// just passed `target`, ignoring whether it's pinned
+    fn can_capture_en_passant(&self, (rank, file): Square) -> bool {
+        // the capturing pawns stand beside the pawn that moved, one rank past
+        // the target from the side to move's point of view
+        let rank = match self.to_move {
+            Color::White => rank - 1,
+            Color::Black => rank + 1,
+        };
+        [file.checked_sub(1), Some(file + 1)]
+            .into_iter()
+            .flatten()
+            .filter(|&f| f < 8)
+            .any(|f| self.holds((rank, f), PieceType::Pawn, self.to_move))
+    }
+}
@@ -2,14 +2,14 @@ use std::{collections::HashMap, io::Read};
 
 use eframe::App;
 use egui::{
-    plot::{Line, Plot, PlotBounds, PlotPoints},
-    pos2, vec2, Color32, ColorImage, Frame, Pos2, Rect, Rounding, Style,
-    TextureHandle,
+    plot::{Line, Plot, PlotBounds, PlotPoint, PlotPoints, Text, VLine},
+    pos2, vec2, Align2, Color32, ColorImage, Frame, Pos2, Rect, Rounding,
+    Style, TextureHandle,
 };
 use egui_extras::{image::load_svg_bytes_with_size, Column, TableBuilder};
 
 use crate::{
-    board::{self, piece::Piece, Board, Color, PieceType},
+    board::{self, piece::Piece, Board, Phase, PieceType, Undo},
     pgn::Game,
 };
 
@@ -18,19 +18,29 @@ pub(crate) struct MyApp {
 
     game: Game,
 
-    /// the current move in `game`, set to `None` once the game is finished
-    cur_move: Option<usize>,
+    /// the position the game starts from, for numbering the moves
+    start: Board,
 
-    cur_color: Color,
-
-    /// cache of previous boards for going backwards through a game
-    boards: Vec<Board>,
+    /// the records for taking back each of the moves played on `board` so
+    /// far, which are the first `undos.len()` moves of `game`
+    undos: Vec<Undo>,
 
     scores: Vec<[f64; 2]>,
 
     /// maximum absolute score in `scores`
     score_max: f64,
 
+    /// the material imbalances that appear during the game, labeled on the
+    /// plot at the ply where each one first appears
+    imbalances: Vec<(usize, String)>,
+
+    /// the ply where each phase of the game starts
+    phases: Vec<(usize, Phase)>,
+
+    /// whether the board is drawn from Black's side, with the eighth rank at
+    /// the bottom
+    flipped: bool,
+
     /// map of piece SVGs, initialized when `self` is created.
     piece_images: HashMap<Piece, ColorImage>,
 
@@ -42,22 +52,13 @@ impl App for MyApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         // key handling
         if ctx.input(|i| i.key_pressed(egui::Key::ArrowRight)) {
-            if let Some(m) = self.cur_move {
-                self.make_move(m);
-            }
+            self.make_move();
         }
         if ctx.input(|i| i.key_pressed(egui::Key::ArrowLeft)) {
-            if let Some(m) = self.cur_move {
-                if m != 0 {
-                    self.board = self.boards[m - 1].clone();
-                    self.cur_move = Some(m - 1);
-                    self.cur_color = self.cur_color.other();
-                }
-            } else {
-                let m = self.game.moves.len() - 1;
-                self.board = self.boards[m].clone();
-                self.cur_move = Some(m);
-            }
+            self.unmake_move();
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::F)) {
+            self.flipped = !self.flipped;
         }
 
         // top panel
@@ -68,6 +69,12 @@ impl App for MyApp {
                         frame.close();
                     }
                 });
+                ui.menu_button("View", |ui| {
+                    if ui.button("Flip board").clicked() {
+                        self.flipped = !self.flipped;
+                        ui.close_menu();
+                    }
+                });
             });
         });
 
@@ -87,50 +94,44 @@ impl App for MyApp {
                     .column(Column::auto())
                     .column(Column::auto())
                     .body(|mut body| {
-                        let mut moves =
-                            self.game.moves.clone().into_iter().array_chunks();
-                        let mut i = 1;
-                        for [w, b] in moves.by_ref() {
-                            body.row(30.0, |mut row| {
-                                row.col(|ui| {
+                        let moves = self.game.main.moves.clone();
+                        let start = &self.start;
+                        let first = start.full_move();
+                        // when Black moves first, the first row starts with an
+                        // empty White column
+                        let skip = start.to_move().is_black() as usize;
+                        let rows = (moves.len() + skip).div_ceil(2);
+                        for row in 0..rows {
+                            body.row(30.0, |mut cols| {
+                                cols.col(|ui| {
+                                    let i = first + row;
                                     ui.label(format!("{i}"));
                                 });
-                                row.col(|ui| {
-                                    if ui.button(format!("{w}")).clicked() {
-                                        let n = 2 * (i - 1) + 1;
-                                        self.get_board(n);
-                                        self.board = self.boards[n].clone();
-                                        self.cur_move = Some(n);
-                                        self.cur_color = Color::Black;
-                                    }
-                                });
-                                row.col(|ui| {
-                                    if ui.button(format!("{b}")).clicked() {
-                                        let n = 2 * i;
-                                        self.get_board(n);
-                                        self.board = self.boards[n].clone();
-                                        self.cur_move = Some(n);
-                                        self.cur_color = Color::White;
-                                    }
-                                });
-                                i += 1;
-                            });
-                        }
-                        match moves.into_remainder() {
-                            Some(mut n) => match n.next() {
-                                Some(w) => {
-                                    body.row(30.0, |mut row| {
-                                        row.col(|ui| {
-                                            ui.label(format!("{i}"));
-                                        });
-                                        row.col(|ui| {
-                                            ui.label(format!("{w}"));
-                                        });
+                                for col in 0..2 {
+                                    cols.col(|ui| {
+                                        let Some(n) =
+                                            (2 * row + col).checked_sub(skip)
+                                        else {
+                                            ui.label("...");
+                                            return;
+                                        };
+                                        if n >= moves.len() {
+                                            return;
+                                        }
+                                        let mut button =
+                                            ui.button(self.label(n));
+                                        let notes = self.notes(n);
+                                        if !notes.is_empty() {
+                                            button = button.on_hover_text(
+                                                notes.join("\n"),
+                                            );
+                                        }
+                                        if button.clicked() {
+                                            self.goto(n + 1);
+                                        }
                                     });
                                 }
-                                None => (),
-                            },
-                            None => (),
+                            });
                         }
                     });
             });
@@ -144,6 +145,26 @@ impl App for MyApp {
                     Line::new(PlotPoints::new(self.scores.clone()))
                         .color(Color32::from_rgb(200, 100, 100)),
                 );
+                for (ply, phase) in &self.phases {
+                    plot_ui.vline(VLine::new(*ply as f64).color(Color32::GRAY));
+                    plot_ui.text(
+                        Text::new(
+                            PlotPoint::new(*ply as f64, min),
+                            format!(" {phase}"),
+                        )
+                        .anchor(Align2::LEFT_TOP),
+                    );
+                }
+                // next to the eval line where each imbalance appears
+                for (ply, label) in &self.imbalances {
+                    let Some(&[x, y]) = self.scores.get(*ply) else {
+                        continue;
+                    };
+                    plot_ui.text(
+                        Text::new(PlotPoint::new(x, y), label)
+                            .anchor(Align2::LEFT_BOTTOM),
+                    );
+                }
             });
         });
     }
@@ -160,7 +181,7 @@ impl MyApp {
                 _ => unreachable!(),
             };
             for piece in ['B', 'K', 'N', 'P', 'Q', 'R'] {
-                let typ = PieceType::from(piece);
+                let typ = PieceType::try_from(piece).unwrap();
                 let filename = format!("assets/{c}{piece}.svg");
                 let mut f = std::fs::File::open(filename).unwrap();
                 let mut buf = Vec::new();
@@ -184,16 +205,46 @@ impl MyApp {
             }
         }
 
-        Self {
+        let mut app = Self {
             board: board.clone(),
+            imbalances: Vec::new(),
+            phases: Vec::new(),
+            flipped: false,
             piece_images,
             pieces: HashMap::new(),
             game,
             scores: out,
             score_max,
-            cur_move: Some(0),
-            cur_color: Color::White,
-            boards: vec![board],
+            start: board,
+            undos: Vec::new(),
+        };
+        app.annotate();
+        app
+    }
+
+    /// replay the game from `self.start`, recording the plies where each
+    /// material imbalance first appears and where each phase of the game
+    /// starts
+    fn annotate(&mut self) {
+        let mut board = self.start.clone();
+        let mut last = Vec::new();
+        for ply in 0..=self.game.main.moves.len() {
+            let current = board.material().imbalances();
+            for imbalance in &current {
+                if !last.contains(imbalance) {
+                    self.imbalances.push((ply, imbalance.to_string()));
+                }
+            }
+            last = current;
+            let phase = board.phase();
+            if self.phases.last().is_none_or(|&(_, p)| p != phase) {
+                self.phases.push((ply, phase));
+            }
+            if let Some(m) = self.game.main.moves.get(ply) {
+                board
+                    .make_move(m)
+                    .expect("moves are checked before starting the GUI");
+            }
         }
     }
 
@@ -216,7 +267,13 @@ impl MyApp {
                 ui.painter().rect_filled(rect, Rounding::none(), color);
                 color = colors.next().unwrap();
 
-                match self.board[(7 - rank, file)] {
+                // turning the board around keeps the colors of the squares
+                let sq = if self.flipped {
+                    (rank, 7 - file)
+                } else {
+                    (7 - rank, file)
+                };
+                match self.board[sq] {
                     p @ Piece::Some { .. } => {
                         let texture =
                             self.pieces.entry(p).or_insert_with(|| {
@@ -240,31 +297,58 @@ impl MyApp {
         }
     }
 
-    /// make the `m`th move on `self.board`, keeping `cur_color`, `cur_move`,
-    /// and `boards` up to date
-    fn make_move(&mut self, m: usize) {
-        self.board.make_move(&self.game.moves[m], self.cur_color);
-        if self.boards.len() <= m + 2 {
-            self.boards.resize(m + 2, Board::default());
-        }
-        self.boards[m + 1] = self.board.clone();
-        if m + 1 < self.game.moves.len() {
-            self.cur_move = Some(m + 1);
-            self.cur_color = self.cur_color.other();
-        } else {
-            self.cur_move = None;
+    /// the `n`th move of the game as shown in the move list, followed by any
+    /// suffix annotations like `!?`
+    fn label(&self, n: usize) -> String {
+        let mut ret = self.game.main.moves[n].to_string();
+        if let Some(a) = self.game.main.annotations.get(n) {
+            ret.extend(a.nags.iter().filter_map(|nag| nag.glyph()));
         }
+        ret
     }
 
-    /// get the `n`th board from self.boards or make moves until it can be
-    /// gotten
-    fn get_board(&mut self, n: usize) {
-        if self.boards.get(n).is_none() {
-            let mut cur = self.cur_move.unwrap();
-            while cur <= n + 1 {
-                self.make_move(cur);
-                cur += 1;
+    /// the comments, clock time, and evaluation given for the `n`th move of
+    /// the game and the variations replacing it, for showing when hovering
+    /// over the move
+    fn notes(&self, n: usize) -> Vec<String> {
+        let mut ret = Vec::new();
+        if let Some(a) = self.game.main.annotations.get(n) {
+            ret.extend(a.before.iter().chain(&a.after).cloned());
+            ret.extend(a.describe_commands());
+        }
+        for v in &self.game.main.variations {
+            if v.start == n {
+                ret.push(format!("({v})"));
             }
         }
+        ret
+    }
+
+    /// play the next move of the game on `self.board`, if there is one
+    fn make_move(&mut self) {
+        if let Some(m) = self.game.main.moves.get(self.undos.len()) {
+            let undo = self
+                .board
+                .make_move(m)
+                .expect("moves are checked before starting the GUI");
+            self.undos.push(undo);
+        }
+    }
+
+    /// take back the last move played on `self.board`, if any
+    fn unmake_move(&mut self) {
+        if let Some(undo) = self.undos.pop() {
+            self.board.unmake_move(&undo);
+        }
+    }
+
+    /// show the board after the first `n` moves of the game
+    fn goto(&mut self, n: usize) {
+        while self.undos.len() > n {
+            self.unmake_move();
+        }
+        while self.undos.len() < n {
+            self.make_move();
+        }
     }
 }
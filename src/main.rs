@@ -1,12 +1,12 @@
-#![feature(iter_array_chunks, array_chunks, let_chains, lazy_cell)]
+#![feature(let_chains, lazy_cell)]
 
 use std::sync::LazyLock;
 use std::time::Instant;
 
-use clap::{arg, value_parser, Command};
+use clap::{arg, value_parser, ArgAction, Command};
 
-use crate::board::{Board, Color};
-use crate::pgn::Pgn;
+use crate::board::{Board, Replay};
+use crate::pgn::{GameError, Pgn};
 use crate::stockfish::Stockfish;
 
 pub mod board;
@@ -17,7 +17,30 @@ mod stockfish;
 struct Args {
     depth: usize,
     gui: bool,
+
+    /// replay the input this many times and report the speed instead of
+    /// scoring it
+    bench: Option<usize>,
+
+    /// the position and depth to run perft on instead of scoring the input
+    perft: Option<(Board, usize)>,
+
+    /// print the input as export-format PGN instead of scoring it
+    export: bool,
+
+    /// skip games that fail to parse or replay instead of stopping, and
+    /// report them at the end
+    keep_going: bool,
+
     input: Pgn,
+
+    /// the number of each game in `input` in the original input, counting
+    /// from 1, which skips over the games that failed to parse
+    numbers: Vec<usize>,
+
+    /// descriptions of the games left out of `input` because they failed to
+    /// parse or transform, when `keep_going` is set
+    skipped: Vec<String>,
 }
 
 impl Args {
@@ -33,20 +56,125 @@ impl Args {
                     .value_parser(value_parser!(bool))
                     .default_value("false"),
             )
+            .arg(
+                arg!(-b --bench <N> "Replay the input N times and time it")
+                    .value_parser(value_parser!(usize)),
+            )
+            .arg(
+                arg!(-e --export "Print the input as PGN in export format")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                arg!(-t --transform <TRANSFORM>)
+                    .help("Flip the colors of or mirror the input games first")
+                    .value_parser(["flip", "mirror"]),
+            )
+            .arg(
+                arg!(-k - -"keep-going")
+                    .help("Skip games with errors and list them at the end")
+                    .action(ArgAction::SetTrue),
+            )
             .arg(arg!([input] "PGN file to score"))
+            .subcommand(
+                Command::new("perft")
+                    .about("Count the legal move paths to the given depth")
+                    .arg(arg!(<fen> "FEN of the position, or `startpos`"))
+                    .arg(
+                        arg!(<depth> "Number of plies to search")
+                            .value_parser(value_parser!(u32).range(1..)),
+                    ),
+            )
             .get_matches();
         let depth = *args.get_one::<usize>("depth").unwrap();
         let gui = *args.get_one::<bool>("gui").unwrap();
+        let bench = args.get_one::<usize>("bench").copied();
+        let export = args.get_flag("export");
+        let keep_going = args.get_flag("keep-going");
+        let transform = args.get_one::<String>("transform");
+        if let Some(("perft", sub)) = args.subcommand() {
+            let fen = sub.get_one::<String>("fen").unwrap();
+            let board = match fen.as_str() {
+                "startpos" => Ok(Board::new()),
+                fen => Board::from_fen(fen),
+            };
+            let board = match board {
+                Ok(b) => b,
+                Err(e) => {
+                    eprintln!("invalid FEN: {e}");
+                    std::process::exit(1);
+                }
+            };
+            let plies = *sub.get_one::<u32>("depth").unwrap() as usize;
+            return Self {
+                depth,
+                gui,
+                bench,
+                perft: Some((board, plies)),
+                export,
+                keep_going,
+                input: Pgn::default(),
+                numbers: Vec::new(),
+                skipped: Vec::new(),
+            };
+        }
         let input = args.get_one::<String>("input");
-        let input = match input {
-            Some(f) => Pgn::load(f).unwrap(),
-            None => Pgn::read(&mut std::io::stdin()).unwrap(),
+        let stdin = std::io::stdin();
+        let input = match (input, keep_going) {
+            (Some(f), false) => Pgn::load(f).map(|pgn| (pgn, Vec::new())),
+            (Some(f), true) => Pgn::load_lenient(f),
+            (None, false) => Pgn::read(stdin).map(|pgn| (pgn, Vec::new())),
+            (None, true) => Pgn::read_lenient(stdin),
+        };
+        let (input, errors) = match input {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("failed to read PGN: {e}");
+                std::process::exit(1);
+            }
         };
-        if input.games.is_empty() {
+        if input.games.is_empty() && errors.is_empty() {
             eprintln!("no games in input");
             std::process::exit(0);
         }
-        Self { depth, gui, input }
+        let errors: Vec<_> =
+            errors.into_iter().map(|(i, e)| (i + 1, e)).collect();
+        let mut skipped: Vec<_> = errors
+            .iter()
+            .map(|(n, e)| format!("game {n}: {e}"))
+            .collect();
+        // the numbers of the games that did parse
+        let parsed = (1..).filter(|n| errors.iter().all(|(m, _)| m != n));
+        let mut games = Vec::new();
+        let mut numbers = Vec::new();
+        for (n, game) in parsed.zip(input.games) {
+            let game = match transform.map(String::as_str) {
+                Some("flip") => game.flipped(),
+                Some(_) => game.mirrored(),
+                None => Ok(game),
+            };
+            match game {
+                Ok(game) => {
+                    games.push(game);
+                    numbers.push(n);
+                }
+                Err(e) if keep_going => skipped.push(format!("game {n}: {e}")),
+                Err(e) => {
+                    eprintln!("error in game {n}: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Self {
+            depth,
+            gui,
+            bench,
+            perft: None,
+            export,
+            keep_going,
+            input: Pgn { games },
+            numbers,
+            skipped,
+        }
     }
 }
 
@@ -59,38 +187,175 @@ fn score_game(
     stockfish: &mut Stockfish,
     game: &pgn::Game,
     depth: usize,
-) -> Vec<f64> {
-    let mut ret = Vec::with_capacity(game.moves.len());
-    let mut board = Board::new();
+) -> Result<Vec<f64>, GameError> {
+    let mut ret = Vec::with_capacity(game.main.moves.len());
+    let start = game.starting_position()?;
+    let position = if start == Board::new() {
+        String::from("startpos")
+    } else {
+        format!("fen {}", start.fen())
+    };
+    let mut replay = Replay::new(start);
+    let mut moves = Vec::with_capacity(game.main.moves.len());
+    stockfish.set_option("UCI_Chess960", replay.board().chess960());
     stockfish.new_game();
-    stockfish.start_position();
-    let mut cur = &Color::White;
-    let score = stockfish.get_score(depth, *cur);
-    println!("0 {score}");
-    let mut to_move = [Color::Black, Color::White].iter().cycle();
-    for (i, m) in game.moves.iter().enumerate() {
+    stockfish.set_position(&position, &moves);
+    let analysis = stockfish.analyze(depth, replay.board().to_move());
+    println!("0 {}", analysis.score);
+    for (i, m) in game.main.moves.iter().enumerate() {
         let i = i + 1;
-        board.make_move(m, *cur);
-        cur = to_move.next().unwrap();
-        let fen = board.fen(i);
-        stockfish.set_position(&fen);
-        let score = stockfish.get_score(depth, *cur);
+        let before = replay.board();
+        let mv = game.main.resolve(before, i - 1)?;
+        moves.push(before.uci_move(&mv));
+        // flag moves that simply drop material, with a cheap explanation
+        let loss = before.material_loss(&mv).map(|loss| {
+            let dots = if before.to_move().is_white() {
+                "."
+            } else {
+                "..."
+            };
+            format!("{}{dots} {m} {loss}", before.full_move())
+        });
+        replay.make_move(m)?;
+        stockfish.set_position(&position, &moves);
+        let board = replay.board();
+        let analysis = stockfish.analyze(depth, board.to_move());
+        let score = analysis.score;
         ret.push(score);
         print!("{i} {score:.2}");
         if *DEBUG {
-            println!(" {fen}");
+            let pv = board.uci_line_to_san(&analysis.pv).unwrap_or_default();
+            let best = analysis
+                .best_move
+                .and_then(|mv| board.parse_uci(&mv).ok())
+                .map_or_else(|| String::from("-"), |mv| board.san(&mv));
+            // the clock and evaluation recorded in the PGN, if any
+            let recorded = game
+                .main
+                .annotations
+                .get(i - 1)
+                .map(|a| a.describe_commands())
+                .unwrap_or_default();
+            print!(" {} best {best} pv {}", board.fen(), pv.join(" "));
+            if !recorded.is_empty() {
+                print!(" ({})", recorded.join(", "));
+            }
+            println!();
         } else {
             println!();
         }
+        if let Some(loss) = loss {
+            eprintln!("{loss}");
+        }
+        // nothing after a draw that either player could claim matters
+        if let Some(draw) = replay.draw() {
+            if draw.is_automatic() {
+                eprintln!("game drawn by {draw} after half move {i}");
+            } else {
+                eprintln!("draw by {draw} claimable after half move {i}");
+            }
+            break;
+        }
+    }
+    Ok(ret)
+}
+
+/// replay every game in `games` `iterations` times without scoring, and report
+/// the number of games replayed per second
+fn bench(games: &[pgn::Game], iterations: usize) {
+    let now = Instant::now();
+    for _ in 0..iterations {
+        for game in games {
+            std::hint::black_box(game.replay().ok());
+        }
     }
-    ret
+    let secs = now.elapsed().as_secs_f64();
+    let n = iterations * games.len();
+    println!(
+        "replayed {n} games in {secs:.2} sec ({:.1} games/sec)",
+        n as f64 / secs
+    );
+}
+
+/// list the games that couldn't be scored or exported, if there were any
+fn report_skipped(skipped: &[String]) {
+    if !skipped.is_empty() {
+        eprintln!("skipped {} games:", skipped.len());
+        for s in skipped {
+            eprintln!("  {s}");
+        }
+    }
+}
+
+/// print each game in `args.input` as PGN in export format, separated by
+/// blank lines. games with illegal moves are skipped if `keep_going` is set,
+/// and listed on stderr along with the ones that failed to parse or transform
+fn export(args: &Args) {
+    let mut skipped = args.skipped.clone();
+    let mut first = true;
+    for (&g, game) in args.numbers.iter().zip(&args.input.games) {
+        match game.to_pgn() {
+            Ok(pgn) => {
+                if !first {
+                    println!();
+                }
+                print!("{pgn}");
+                first = false;
+            }
+            Err(e) if args.keep_going => skipped.push(format!("game {g}: {e}")),
+            Err(e) => {
+                eprintln!("error in game {g}: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    report_skipped(&skipped);
+}
+
+/// print the number of leaf nodes `depth` plies below each legal move in
+/// `board`, followed by the total
+fn perft(board: &Board, depth: usize) {
+    let now = Instant::now();
+    let mut total = 0;
+    for (mv, n) in board.divide(depth) {
+        println!("{}: {n}", board.uci_move(&mv));
+        total += n;
+    }
+    println!("\nNodes searched: {total}");
+    eprintln!("finished after {:.1} sec", now.elapsed().as_secs_f64());
 }
 
 fn main() {
     let args = Args::new();
 
+    if let Some((board, depth)) = &args.perft {
+        perft(board, *depth);
+        return;
+    }
+
+    if args.export {
+        export(&args);
+        return;
+    }
+
+    if let Some(iterations) = args.bench {
+        bench(&args.input.games, iterations);
+        return;
+    }
+
     if args.gui {
-        let game = args.input.games[0].clone();
+        let Some(game) = args.input.games.first().cloned() else {
+            eprintln!("no valid games in input");
+            std::process::exit(1);
+        };
+        // the GUI steps through the moves freely, so check them all up front
+        if let Err(e) = game.replay() {
+            eprintln!("invalid game: {e}");
+            std::process::exit(1);
+        }
+        let board = game
+            .starting_position()
+            .expect("replay already checked the starting position");
         // let mut stockfish = Stockfish::new();
         // let scores = score_game(&mut stockfish, &game, args.depth);
         let scores = vec![
@@ -107,9 +372,7 @@ fn main() {
         eframe::run_native(
             PROGRAM_TITLE,
             eframe::NativeOptions::default(),
-            Box::new(|_cc| {
-                Box::new(gui::MyApp::new(Board::new(), game, scores))
-            }),
+            Box::new(move |_cc| Box::new(gui::MyApp::new(board, game, scores))),
         )
         .unwrap();
         return;
@@ -117,17 +380,39 @@ fn main() {
 
     let mut stockfish = Stockfish::new();
 
-    for (g, pgn) in args.input.games.iter().enumerate() {
+    // descriptions of the games that couldn't be scored
+    let mut skipped = args.skipped.clone();
+    for (&g, pgn) in args.numbers.iter().zip(&args.input.games) {
         let (w, b) = pgn.players();
-        eprintln!("starting game {}: {} - {}", g + 1, w, b);
+        eprintln!("starting game {g}: {w} - {b}");
         let now = Instant::now();
 
-        score_game(&mut stockfish, pgn, args.depth);
+        if let Err(e) = score_game(&mut stockfish, pgn, args.depth) {
+            if !args.keep_going {
+                eprintln!("error in game {g}: {e}");
+                std::process::exit(1);
+            }
+            eprintln!("skipping game {g}: {e}\n");
+            skipped.push(format!("game {g} ({w} - {b}): {e}"));
+            continue;
+        }
+
+        // scoring stops at a draw, so the rest of the game may still hold an
+        // illegal move
+        match pgn.validate() {
+            Ok(mismatches) => {
+                for m in mismatches {
+                    eprintln!("warning: game {g}: {m}");
+                }
+            }
+            Err(e) => eprintln!("warning: game {g}: {e}"),
+        }
 
         eprintln!(
-            "finished game {} after {:.1} sec\n",
-            g + 1,
+            "finished game {g} after {:.1} sec\n",
             now.elapsed().as_millis() as f64 / 1000.0
         );
     }
+
+    report_skipped(&skipped);
 }
@@ -1,27 +1,322 @@
 use std::{
     collections::HashMap,
+    fmt::Display,
     fs::File,
     io::{self, BufRead, BufReader, Read},
     mem::take,
     path::Path,
     str::FromStr,
+    time::Duration,
 };
 
-use self::mov::Move;
+use self::commands::Eval;
+use self::mov::{Indicator, Move, MoveParseError};
+use self::nag::Nag;
+use crate::board::{Board, FenError, IllegalMove, LegalMove};
 
+pub mod commands;
+pub mod export;
 pub mod mov;
+pub mod nag;
+pub mod transform;
+pub mod validate;
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
+
+/// a problem found while reading a PGN, at the 1-based `line` and `col`
+#[derive(Debug)]
+pub struct PgnError {
+    pub line: usize,
+    pub col: usize,
+    pub kind: PgnErrorKind,
+}
+
+#[derive(Debug)]
+pub enum PgnErrorKind {
+    Io(io::Error),
+
+    /// a line starting with `[` that isn't a tag pair
+    Tag(String),
+
+    Move(MoveParseError),
+
+    /// the movetext of a game didn't end with a result
+    MissingResult,
+
+    /// a `(` without a matching `)`, or the other way around
+    UnmatchedParen(char),
+
+    /// a variation that doesn't follow a move for it to replace
+    MisplacedVariation,
+
+    /// a `{` comment without a closing `}`
+    UnclosedComment,
+
+    /// a token starting with `$`, `!`, or `?` that isn't a NAG or a suffix
+    /// annotation
+    Nag(String),
+
+    /// a NAG that doesn't follow a move for it to annotate
+    MisplacedNag,
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { line, col, kind } = self;
+        match kind {
+            PgnErrorKind::Io(e) if *line == 0 => write!(f, "{e}"),
+            PgnErrorKind::Io(e) => write!(f, "line {line}: {e}"),
+            PgnErrorKind::Tag(s) => {
+                write!(f, "line {line}, column {col}: invalid tag `{s}`")
+            }
+            PgnErrorKind::Move(e) => {
+                write!(f, "line {line}, column {col}: {e}")
+            }
+            PgnErrorKind::MissingResult => {
+                write!(f, "line {line}, column {col}: missing game result")
+            }
+            PgnErrorKind::UnmatchedParen(c) => {
+                write!(f, "line {line}, column {col}: unmatched `{c}`")
+            }
+            PgnErrorKind::MisplacedVariation => write!(
+                f,
+                "line {line}, column {col}: variation without a move to \
+                 replace"
+            ),
+            PgnErrorKind::UnclosedComment => {
+                write!(f, "line {line}, column {col}: unclosed comment")
+            }
+            PgnErrorKind::Nag(s) => {
+                write!(f, "line {line}, column {col}: invalid NAG `{s}`")
+            }
+            PgnErrorKind::MisplacedNag => write!(
+                f,
+                "line {line}, column {col}: NAG without a move to annotate"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// an error opening the file, before any lines were read
+impl From<io::Error> for PgnError {
+    fn from(e: io::Error) -> Self {
+        Self {
+            line: 0,
+            col: 0,
+            kind: PgnErrorKind::Io(e),
+        }
+    }
+}
+
+/// the ways replaying a [Game] can fail
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameError {
+    /// the `FEN` tag doesn't hold a valid position
+    Fen(FenError),
+
+    Illegal(IllegalMove),
+
+    /// the game can't be mirrored, because someone castles in it and castling
+    /// isn't symmetric
+    Castling,
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::Fen(e) => write!(f, "invalid starting position: {e}"),
+            GameError::Illegal(e) => write!(f, "{e}"),
+            GameError::Castling => write!(f, "castling can't be mirrored"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<FenError> for GameError {
+    fn from(e: FenError) -> Self {
+        Self::Fen(e)
+    }
+}
+
+impl From<IllegalMove> for GameError {
+    fn from(e: IllegalMove) -> Self {
+        Self::Illegal(e)
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Game {
-    pub moves: Vec<Move>,
+    /// the moves of the game, along with their comments and the variations
+    /// branching off of them. its `start` is always 0
+    pub main: Variation,
+
     pub result: String,
     pub tags: HashMap<String, String>,
 }
 
+/// a recursive annotation variation (RAV), a sequence of moves played instead
+/// of one of the moves of the game or of an enclosing variation. the main
+/// line of a game is stored the same way
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Variation {
+    /// the index of the move this variation replaces in the enclosing line,
+    /// so that it starts from the position before that move
+    pub start: usize,
+
+    pub moves: Vec<Move>,
+
+    /// each move in `moves` as written, for reporting it if it's illegal
+    pub sans: Vec<String>,
+
+    /// the check or checkmate marker written after each move in `moves`
+    pub checks: Vec<Option<Indicator>>,
+
+    /// the comments attached to each move in `moves`
+    pub annotations: Vec<Annotations>,
+
+    /// the variations nested inside this one, with their `start`s indexing
+    /// into this variation's `moves`
+    pub variations: Vec<Variation>,
+}
+
+/// the commentary attached to a single move of a game or variation
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotations {
+    /// the comments written before the move, which only happens at the start
+    /// of a game or variation or right after a variation
+    pub before: Vec<String>,
+
+    /// the comments written after the move
+    pub after: Vec<String>,
+
+    /// the NAGs written after the move, including the ones given as suffix
+    /// annotations like `!?`
+    pub nags: Vec<Nag>,
+
+    /// the time left on the mover's clock after the move, from a `[%clk
+    /// ...]` command
+    pub clock: Option<Duration>,
+
+    /// the time spent on the move, from an `[%emt ...]` command
+    pub elapsed: Option<Duration>,
+
+    /// the evaluation of the position after the move, from an `[%eval ...]`
+    /// command, usually added by the site the game was played on
+    pub eval: Option<Eval>,
+}
+
+impl Variation {
+    /// resolve move `i` of `self` on `board`, reporting it as it was written
+    /// if it's illegal
+    pub(crate) fn resolve(
+        &self,
+        board: &Board,
+        i: usize,
+    ) -> Result<LegalMove, IllegalMove> {
+        board.resolve(&self.moves[i]).map_err(|e| IllegalMove {
+            san: self.sans[i].clone(),
+            ..e
+        })
+    }
+}
+
+/// the moves of a variation, without move numbers, with their comments in
+/// braces and their own variations in parentheses after the moves they replace
+impl Display for Variation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut words = Vec::new();
+        let mut variations = self.variations.iter().peekable();
+        let moves = self.moves.iter().zip(&self.checks).zip(&self.annotations);
+        for (i, ((m, check), annotations)) in moves.enumerate() {
+            let comment = |c| format!("{{{c}}}");
+            words.extend(annotations.before.iter().map(comment));
+            let mut word = m.to_string();
+            if let Some(check) = check {
+                word.push_str(&check.to_string());
+            }
+            // the first suffix annotation goes on the move itself, as
+            // readers expect
+            let mut glyph = true;
+            for nag in &annotations.nags {
+                match nag.glyph() {
+                    Some(g) if glyph => word.push_str(g),
+                    _ => word.push_str(&format!(" {nag}")),
+                }
+                glyph = false;
+            }
+            words.push(word);
+            words.extend(annotations.after.iter().map(comment));
+            while let Some(v) = variations.next_if(|v| v.start == i) {
+                words.push(format!("({v})"));
+            }
+        }
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// play the moves of `line` on `board`, checking each of the variations
+/// branching off of them from the position before the move it replaces, and
+/// return the final position
+fn replay_line(
+    mut board: Board,
+    line: &Variation,
+) -> Result<Board, IllegalMove> {
+    let mut variations = line.variations.iter().peekable();
+    for i in 0..line.moves.len() {
+        while let Some(v) = variations.next_if(|v| v.start == i) {
+            replay_line(board.clone(), v)?;
+        }
+        let mv = line.resolve(&board, i)?;
+        board.play(&mv);
+    }
+    Ok(board)
+}
+
 impl Game {
+    /// return the value of the tag `name` with its surrounding quotes removed
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.get(name).map(|v| v.trim_matches('"'))
+    }
+
+    /// return the position the game starts from. this is the position in the
+    /// `FEN` tag if there is one, unless `SetUp` is explicitly "0", and the
+    /// standard starting position otherwise. a `Variant` tag naming Chess960
+    /// marks the position as Chess960 even if it looks like standard chess
+    pub fn starting_position(&self) -> Result<Board, FenError> {
+        let mut ret = match (self.tag("SetUp"), self.tag("FEN")) {
+            (Some("0"), _) | (_, None) => Board::new(),
+            (_, Some(fen)) => Board::from_fen(fen)?,
+        };
+        if self.is_chess960() {
+            ret.set_chess960(true);
+        }
+        Ok(ret)
+    }
+
+    /// report whether the `Variant` tag names Chess960, under any of its
+    /// usual spellings
+    pub fn is_chess960(&self) -> bool {
+        self.tag("Variant").is_some_and(|v| {
+            let v: String = v
+                .chars()
+                .filter(char::is_ascii_alphanumeric)
+                .map(|c| c.to_ascii_lowercase())
+                .collect();
+            matches!(v.as_str(), "chess960" | "fischerandom" | "fischerrandom")
+        })
+    }
+
+    /// play through every move of the game, including its variations,
+    /// returning the final position of the main line
+    pub fn replay(&self) -> Result<Board, GameError> {
+        let board = self.starting_position()?;
+        Ok(replay_line(board, &self.main)?)
+    }
+
     pub fn players(&self) -> (String, String) {
         let white = self
             .tags
@@ -57,86 +352,365 @@ impl State {
     }
 }
 
-/// parse the movetext section of a game into a series of Moves and the game's
-/// result
-fn parse_movetext(game: String) -> (Vec<Move>, String) {
-    let mut in_brackets = false;
-    let mut in_parens = false;
-    let mut ret = String::new();
-    for c in game.chars() {
-        if c == '{' {
-            in_brackets = true;
-        } else if in_brackets {
-            if c == '}' {
-                in_brackets = false;
+/// the pieces movetext is made of, apart from the whitespace between them
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// a move, move number, or result
+    Word(String),
+
+    /// the text of a `{...}` comment or a `;` comment running to the end of
+    /// the line
+    Comment(String),
+
+    Open,
+    Close,
+}
+
+/// split the movetext `lines`, along with their line numbers, into tokens
+/// along with their lines and columns
+fn tokenize(
+    lines: &[(usize, String)],
+) -> Result<Vec<(usize, usize, Token)>, PgnError> {
+    let mut tokens = Vec::new();
+    // the comment being read, which may span several lines, and where it
+    // started
+    let mut comment: Option<(usize, usize, String)> = None;
+    for (line, text) in lines {
+        let line = *line;
+        let mut word: Option<(usize, String)> = None;
+        let mut chars = text.chars().enumerate();
+        while let Some((i, c)) = chars.next() {
+            if let Some((l, col, s)) = &mut comment {
+                if c == '}' {
+                    let text = s.trim().to_owned();
+                    tokens.push((*l, *col, Token::Comment(text)));
+                    comment = None;
+                } else {
+                    s.push(c);
+                }
+                continue;
             }
-            continue;
-        } else if c == '(' {
-            in_parens = true;
-        } else if in_parens {
-            if c == ')' {
-                in_parens = false;
+            if !c.is_ascii_whitespace() && !"{;()".contains(c) {
+                word.get_or_insert_with(|| (i + 1, String::new())).1.push(c);
+                continue;
+            }
+            if let Some((col, w)) = word.take() {
+                tokens.push((line, col, Token::Word(w)));
+            }
+            match c {
+                '{' => comment = Some((line, i + 1, String::new())),
+                ';' => {
+                    let s: String = chars.by_ref().map(|(_, c)| c).collect();
+                    let text = s.trim().to_owned();
+                    tokens.push((line, i + 1, Token::Comment(text)));
+                }
+                '(' => tokens.push((line, i + 1, Token::Open)),
+                ')' => tokens.push((line, i + 1, Token::Close)),
+                _ => {}
             }
-            continue;
-        } else {
-            ret.push(c);
         }
+        if let Some((col, w)) = word {
+            tokens.push((line, col, Token::Word(w)));
+        }
+        // keep the words on either side of a line break apart
+        if let Some((_, _, s)) = &mut comment {
+            s.push(' ');
+        }
+    }
+    if let Some((line, col, _)) = comment {
+        return Err(PgnError {
+            line,
+            col,
+            kind: PgnErrorKind::UnclosedComment,
+        });
     }
+    Ok(tokens)
+}
+
+/// the game termination markers that end the movetext of every game
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
 
-    let mut chunks: Vec<_> = ret.split_ascii_whitespace().collect();
+/// parse the movetext section of a game, given as its lines along with their
+/// line numbers, into its main line and result. `end` is the line number the
+/// game ended on
+fn parse_movetext(
+    lines: &[(usize, String)],
+    end: usize,
+) -> Result<(Variation, String), PgnError> {
+    let mut tokens = tokenize(lines)?;
 
     // delete the result from the end
-    let result = chunks.pop().expect("failed to load empty PGN");
+    let result = match tokens.pop() {
+        Some((_, _, Token::Word(w))) if RESULTS.contains(&w.as_str()) => w,
+        _ => {
+            return Err(PgnError {
+                line: end,
+                col: 1,
+                kind: PgnErrorKind::MissingResult,
+            })
+        }
+    };
+
+    let main = parse_line(&mut tokens.into_iter(), None)?;
+    Ok((main, result))
+}
+
+/// parse moves from `tokens` until the end of the variation opened by the
+/// `(` at `open`, or until the end of the movetext for the main line, when
+/// `open` is `None`.
+///
+/// a comment right after a move belongs to that move, and any other comment
+/// belongs to the next move, like one at the start of a variation or after
+/// one ends
+fn parse_line(
+    tokens: &mut impl Iterator<Item = (usize, usize, Token)>,
+    open: Option<(usize, usize)>,
+) -> Result<Variation, PgnError> {
+    let mut ret = Variation::default();
+    // the comments waiting for the next move
+    let mut before = Vec::new();
+    // whether the last token was a move, possibly followed by its number
+    let mut after_move = false;
+    loop {
+        let Some((line, col, token)) = tokens.next() else {
+            if let Some((line, col)) = open {
+                return Err(PgnError {
+                    line,
+                    col,
+                    kind: PgnErrorKind::UnmatchedParen('('),
+                });
+            }
+            break;
+        };
+        let m = match token {
+            Token::Comment(s) => {
+                match ret.annotations.last_mut() {
+                    Some(a) if after_move => a.add_comment(s, false),
+                    _ => before.push(s),
+                }
+                continue;
+            }
+            Token::Close if open.is_some() => break,
+            Token::Close => {
+                return Err(PgnError {
+                    line,
+                    col,
+                    kind: PgnErrorKind::UnmatchedParen(')'),
+                });
+            }
+            Token::Open => {
+                let Some(start) = ret.moves.len().checked_sub(1) else {
+                    return Err(PgnError {
+                        line,
+                        col,
+                        kind: PgnErrorKind::MisplacedVariation,
+                    });
+                };
+                let variation = parse_line(tokens, Some((line, col)))?;
+                ret.variations.push(Variation { start, ..variation });
+                after_move = false;
+                continue;
+            }
+            Token::Word(m) => m,
+        };
+        // a move number can be run into its move, like in `12.Nf3`
+        let (col, m) = match strip_move_number(&m) {
+            Some(rest) => (col + m.len() - rest.len(), rest.to_owned()),
+            None => (col, m),
+        };
+        // skip move numbers and the optional en passant marker in `exd6 e.p.`
+        if m.is_empty() || m.starts_with(char::is_numeric) || m == "e.p." {
+            continue;
+        }
+        // a NAG like `$4`, or a suffix annotation set apart from its move
+        if m.starts_with(['$', '!', '?']) {
+            let nag = m.parse().map_err(|()| PgnError {
+                line,
+                col,
+                kind: PgnErrorKind::Nag(m),
+            })?;
+            let Some(a) = ret.annotations.last_mut() else {
+                return Err(PgnError {
+                    line,
+                    col,
+                    kind: PgnErrorKind::MisplacedNag,
+                });
+            };
+            a.nags.push(nag);
+            continue;
+        }
+        let (san, nag) = Nag::from_suffix(&m);
+        let mov = Move::from_str(san).map_err(|e| PgnError {
+            line,
+            col,
+            kind: PgnErrorKind::Move(e),
+        })?;
+        ret.moves.push(mov);
+        ret.sans.push(san.to_owned());
+        ret.checks.push(Indicator::from_san(san));
+        let mut annotations = Annotations {
+            nags: Vec::from_iter(nag),
+            ..Default::default()
+        };
+        for comment in take(&mut before) {
+            annotations.add_comment(comment, true);
+        }
+        ret.annotations.push(annotations);
+        after_move = true;
+    }
+    // comments after a line's last variation go with its last move
+    if let Some(a) = ret.annotations.last_mut() {
+        for comment in before {
+            a.add_comment(comment, false);
+        }
+    }
+    Ok(ret)
+}
+
+/// the rest of `word` after a leading move number like `12.` or `12...`, if
+/// it starts with one
+fn strip_move_number(word: &str) -> Option<&str> {
+    let rest = word.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == word.len() {
+        return None;
+    }
+    let mov = rest.trim_start_matches('.');
+    (mov.len() < rest.len()).then_some(mov)
+}
 
-    let mut moves = Vec::new();
-    for m in chunks {
-        if !m.starts_with(char::is_numeric) {
-            moves.push(Move::from_str(m).unwrap());
+/// report whether a `{` comment is still open at the end of `line`, given
+/// whether one was `open` at its start
+fn comment_open(mut open: bool, line: &str) -> bool {
+    for c in line.chars() {
+        match c {
+            '}' if open => open = false,
+            '{' if !open => open = true,
+            // the rest of the line is a `;` comment
+            ';' if !open => break,
+            _ => {}
         }
     }
+    open
+}
 
-    (moves, result.to_owned())
+/// parse a tag pair like `[Event "?"]` into its name and value
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let line = line.replace(['[', ']'], "");
+    let sp: Vec<_> = line.split_ascii_whitespace().collect();
+    let (name, value) = sp.split_first()?;
+    Some((name.to_string(), value.join(" ")))
+}
+
+/// assemble a game from its `tags` and the `lines` of its movetext, ending
+/// on line `end`, unless one of its tags had an `error`
+fn parse_game(
+    tags: HashMap<String, String>,
+    lines: &[(usize, String)],
+    end: usize,
+    error: Option<PgnError>,
+) -> Result<Game, PgnError> {
+    if let Some(e) = error {
+        return Err(e);
+    }
+    let (main, result) = parse_movetext(lines, end)?;
+    Ok(Game { main, result, tags })
 }
 
 impl Pgn {
-    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PgnError> {
         let f = File::open(path)?;
         Self::read(f)
     }
 
-    pub fn read(r: impl Read) -> Result<Pgn, io::Error> {
+    /// like [Pgn::load], but see [Pgn::read_lenient]
+    pub fn load_lenient(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, Vec<(usize, PgnError)>), PgnError> {
+        let f = File::open(path)?;
+        Self::read_lenient(f)
+    }
+
+    /// read every game in `r`, failing on the first one that can't be parsed
+    pub fn read(r: impl Read) -> Result<Pgn, PgnError> {
+        let games = Self::read_games(r)?;
+        Ok(Self {
+            games: games.into_iter().collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// read every game in `r`, skipping the ones that can't be parsed and
+    /// returning their errors alongside the rest, along with the index of
+    /// each of those games in `r`. only failing to read `r` at all is an
+    /// error
+    pub fn read_lenient(
+        r: impl Read,
+    ) -> Result<(Pgn, Vec<(usize, PgnError)>), PgnError> {
+        let mut games = Vec::new();
+        let mut errors = Vec::new();
+        for (i, game) in Self::read_games(r)?.into_iter().enumerate() {
+            match game {
+                Ok(game) => games.push(game),
+                Err(e) => errors.push((i, e)),
+            }
+        }
+        Ok((Self { games }, errors))
+    }
+
+    fn read_games(
+        r: impl Read,
+    ) -> Result<Vec<Result<Game, PgnError>>, PgnError> {
         let r = BufReader::new(r);
         let mut games = Vec::new();
-        let mut game = String::new();
+        let mut lines = Vec::new();
         let mut tags = HashMap::new();
+        // the first bad tag in the current game
+        let mut error = None;
+        // whether a `{` comment in `lines` is still open, so that a line
+        // starting with `[` belongs to it instead of being a tag
+        let mut in_comment = false;
         use State::*;
         let mut state = Tags;
-        for line in r.lines().flatten() {
-            if line.starts_with('[') {
-                let line = line.replace(['[', ']'], "");
-                let sp: Vec<_> = line.split_ascii_whitespace().collect();
-                tags.insert(sp[0].to_owned(), sp[1..].join(" ").to_owned());
+        let mut n = 0;
+        for line in r.lines() {
+            n += 1;
+            let line = line.map_err(|e| PgnError {
+                line: n,
+                col: 1,
+                kind: PgnErrorKind::Io(e),
+            })?;
+            if state.is_tags() && !in_comment && line.starts_with('[') {
+                match parse_tag(&line) {
+                    Some((name, value)) => {
+                        tags.insert(name, value);
+                    }
+                    None => {
+                        error.get_or_insert(PgnError {
+                            line: n,
+                            col: 1,
+                            kind: PgnErrorKind::Tag(line),
+                        });
+                    }
+                }
             } else if state.is_tags() && line.is_empty() {
                 state = Moves;
             } else if state.is_moves() && line.is_empty() {
                 state = Tags;
-                let (moves, result) = parse_movetext(take(&mut game));
-                games.push(Game {
-                    moves,
-                    result,
-                    tags: take(&mut tags),
-                });
+                in_comment = false;
+                games.push(parse_game(
+                    take(&mut tags),
+                    &take(&mut lines),
+                    n,
+                    error.take(),
+                ));
             } else {
-                game.push_str(&line);
-                game.push(' '); // keep separation from newlines
+                in_comment = comment_open(in_comment, &line);
+                lines.push((n, line));
             }
         }
-        let (moves, result) = parse_movetext(game);
-        games.push(Game {
-            moves,
-            result,
-            tags,
-        });
-        Ok(Self { games })
+        // the input may end with blank lines after the last game
+        if !tags.is_empty() || !lines.is_empty() || error.is_some() {
+            games.push(parse_game(tags, &lines, n, error));
+        }
+        Ok(games)
     }
 }
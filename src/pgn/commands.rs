@@ -0,0 +1,190 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use super::Annotations;
+
+/// an evaluation embedded in a PGN by an `[%eval ...]` command, from White's
+/// point of view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eval {
+    /// a score in pawns, like `[%eval 0.37]`
+    Pawns(f64),
+
+    /// a forced mate in this many moves, negative when Black is mating, like
+    /// `[%eval #-3]`
+    Mate(i32),
+}
+
+impl FromStr for Eval {
+    type Err = ();
+
+    /// parse the argument of an `[%eval ...]` command, ignoring the search
+    /// depth some programs add after a comma, like `0.37,20`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let score = s.split(',').next().unwrap_or_default();
+        match score.strip_prefix('#') {
+            Some(n) => n.parse().map(Self::Mate).map_err(|_| ()),
+            None => score.parse().map(Self::Pawns).map_err(|_| ()),
+        }
+    }
+}
+
+impl Display for Eval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Eval::Pawns(p) => write!(f, "{p:.2}"),
+            Eval::Mate(n) => write!(f, "#{n}"),
+        }
+    }
+}
+
+/// parse a time like `1:02:03`, `0:00:59.5`, or `59.5`, as written in the
+/// `[%clk ...]` and `[%emt ...]` commands
+fn parse_time(s: &str) -> Option<Duration> {
+    let (rest, seconds) = s.rsplit_once(':').unwrap_or(("", s));
+    let (whole, frac) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let mut secs: u64 = whole.parse().ok()?;
+    let mut nanos = 0;
+    if !frac.is_empty() {
+        if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        nanos = format!("{frac:0<9}").parse().ok()?;
+    }
+    // minutes, then hours
+    let mut unit = 60;
+    for field in rest.rsplit(':').filter(|_| !rest.is_empty()) {
+        secs += field.parse::<u64>().ok()? * unit;
+        unit *= 60;
+    }
+    Some(Duration::new(secs, nanos))
+}
+
+/// format `d` like the times in `[%clk ...]` commands, as `H:MM:SS` with any
+/// fraction of a second after a decimal point
+pub fn format_time(d: Duration) -> String {
+    let secs = d.as_secs();
+    let mut ret =
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if d.subsec_nanos() > 0 {
+        let frac = format!("{:09}", d.subsec_nanos());
+        ret.push('.');
+        ret.push_str(frac.trim_end_matches('0'));
+    }
+    ret
+}
+
+impl Annotations {
+    /// attach `comment` to the move, either `before` or after it, pulling any
+    /// `[%clk ...]`, `[%emt ...]`, and `[%eval ...]` commands out into their
+    /// own fields. other commands, like the `[%cal ...]` arrows lichess
+    /// writes, and commands that don't parse stay in the comment, which is
+    /// dropped if nothing else is left
+    pub(crate) fn add_comment(&mut self, comment: String, before: bool) {
+        let mut rest = String::new();
+        let mut s = comment.as_str();
+        let mut found = false;
+        while let Some(start) = s.find("[%")
+            && let Some(len) = s[start..].find(']')
+        {
+            let end = start + len + 1;
+            rest.push_str(&s[..start]);
+            if self.command(&s[start + 2..end - 1]) {
+                found = true;
+            } else {
+                rest.push_str(&s[start..end]);
+            }
+            s = &s[end..];
+        }
+        rest.push_str(s);
+        let comment = if found {
+            rest.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            comment
+        };
+        if comment.is_empty() && found {
+            return;
+        }
+        if before {
+            self.before.push(comment);
+        } else {
+            self.after.push(comment);
+        }
+    }
+
+    /// describe the clock time, time spent, and evaluation stored on `self`,
+    /// for showing alongside the move
+    pub(crate) fn describe_commands(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        if let Some(clock) = self.clock {
+            ret.push(format!("clock {}", format_time(clock)));
+        }
+        if let Some(elapsed) = self.elapsed {
+            ret.push(format!("spent {}", format_time(elapsed)));
+        }
+        if let Some(eval) = self.eval {
+            ret.push(format!("eval {eval}"));
+        }
+        ret
+    }
+
+    /// apply the command `s`, like the `clk 0:00:59.5` of `[%clk 0:00:59.5]`,
+    /// and report whether it was one of the commands stored on `self`
+    fn command(&mut self, s: &str) -> bool {
+        let Some((name, value)) = s.trim().split_once(char::is_whitespace)
+        else {
+            return false;
+        };
+        let value = value.trim();
+        match name {
+            "clk" => parse_time(value).map(|t| self.clock = Some(t)).is_some(),
+            "emt" => {
+                parse_time(value).map(|t| self.elapsed = Some(t)).is_some()
+            }
+            "eval" => value.parse().map(|e| self.eval = Some(e)).is_ok(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times() {
+        for (s, want) in [
+            ("0:00:59.5", Duration::from_millis(59_500)),
+            ("1:02:03", Duration::from_secs(3723)),
+            ("0:00:42", Duration::from_secs(42)),
+            ("3:07", Duration::from_secs(187)),
+            ("0:00:00.25", Duration::from_millis(250)),
+        ] {
+            assert_eq!(parse_time(s), Some(want), "{s}");
+        }
+        for s in ["", "0:xx:00", "0:00:1.-5", "1.2.3"] {
+            assert_eq!(parse_time(s), None, "{s}");
+        }
+        assert_eq!(format_time(Duration::from_millis(59_500)), "0:00:59.5");
+        assert_eq!(format_time(Duration::from_secs(3723)), "1:02:03");
+    }
+
+    #[test]
+    fn commands() {
+        let mut a = Annotations::default();
+        a.add_comment("[%clk 0:00:59.5]".to_owned(), false);
+        a.add_comment(
+            "a blunder [%eval #-3] [%emt 0:00:12] [%csl Gd4]".to_owned(),
+            false,
+        );
+        a.add_comment("[%eval 0.37,20] [%clk soon]".to_owned(), false);
+        assert_eq!(
+            a.describe_commands(),
+            ["clock 0:00:59.5", "spent 0:00:12", "eval 0.37"]
+        );
+        assert_eq!(a.clock, Some(Duration::from_millis(59_500)));
+        assert_eq!(a.elapsed, Some(Duration::from_secs(12)));
+        assert_eq!(a.eval, Some(Eval::Pawns(0.37)));
+        assert_eq!(a.after, ["a blunder [%csl Gd4]", "[%clk soon]"]);
+        assert_eq!(Eval::Mate(-3).to_string(), "#-3");
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt::Write;
+
+use super::{commands::format_time, Annotations, Game, GameError, Variation};
+use crate::board::{Board, Color, IllegalMove};
+
+/// the tags every game has in export format, in the order they come first
+pub(crate) const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// the longest line export format allows, so that it fits in 80 columns
+const LINE_WIDTH: usize = 79;
+
+/// recover a tag value as stored in [Game::tags], like `"a \"b\""`, from its
+/// quotes and escapes
+fn unquote(raw: &str) -> String {
+    let s = raw
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(raw);
+    let mut ret = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => ret.extend(chars.next()),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// quote `value` for a tag pair, escaping any backslashes and quotes in it
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// add the words of the comment `text` to `tokens`, with the braces attached
+/// to the first and last words so that the comment can be wrapped like the
+/// rest of the movetext. a `}` would end a brace comment early, so a comment
+/// containing one, which can only come from a `;` comment, is written as a
+/// `;` comment again, in a single token that [wrap] ends the line after.
+///
+/// a line starting with `[` reads as a tag pair and one starting with `%` is
+/// skipped, so words like the `[%cal ...]` commands stay in one token with
+/// the word before them
+fn push_comment(text: &str, tokens: &mut Vec<String>) {
+    if text.contains('}') {
+        let words: Vec<_> = text.split_whitespace().collect();
+        tokens.push(format!("; {}", words.join(" ")));
+        return;
+    }
+    let mut words: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match words.last_mut() {
+            Some(last) if word.starts_with(['[', '%']) => {
+                last.push(' ');
+                last.push_str(word);
+            }
+            _ => words.push(word.to_owned()),
+        }
+    }
+    match words.as_mut_slice() {
+        [] => tokens.push("{}".to_owned()),
+        [first, .., last] => {
+            first.insert(0, '{');
+            last.push('}');
+        }
+        [word] => *word = format!("{{{word}}}"),
+    }
+    tokens.extend(words);
+}
+
+/// the `[%eval ...]`, `[%clk ...]`, and `[%emt ...]` commands for the fields
+/// of `a`, in a comment of their own. this is a single token, so that line
+/// breaks don't split up the commands
+fn commands(a: &Annotations) -> Option<String> {
+    let mut ret = Vec::new();
+    if let Some(eval) = a.eval {
+        ret.push(format!("[%eval {eval}]"));
+    }
+    if let Some(clock) = a.clock {
+        ret.push(format!("[%clk {}]", format_time(clock)));
+    }
+    if let Some(elapsed) = a.elapsed {
+        ret.push(format!("[%emt {}]", format_time(elapsed)));
+    }
+    (!ret.is_empty()).then(|| format!("{{{}}}", ret.join(" ")))
+}
+
+/// add the movetext for the moves of `line`, played from `board`, to
+/// `tokens`, along with their annotations and the variations branching off of
+/// them
+fn push_line(
+    mut board: Board,
+    line: &Variation,
+    tokens: &mut Vec<String>,
+) -> Result<(), IllegalMove> {
+    let mut variations = line.variations.iter().peekable();
+    let empty = Annotations::default();
+    // Black's moves need their number at the start of a line and after a
+    // comment or variation, so that readers can find their place
+    let mut number = true;
+    for i in 0..line.moves.len() {
+        let a = line.annotations.get(i).unwrap_or(&empty);
+        for comment in &a.before {
+            push_comment(comment, tokens);
+            number = true;
+        }
+
+        let mv = line.resolve(&board, i)?;
+        match board.to_move() {
+            Color::White => tokens.push(format!("{}.", board.full_move())),
+            Color::Black if number => {
+                tokens.push(format!("{}...", board.full_move()))
+            }
+            Color::Black => {}
+        }
+        tokens.push(board.san(&mv));
+        number = false;
+        tokens.extend(a.nags.iter().map(|nag| nag.to_string()));
+        for comment in &a.after {
+            push_comment(comment, tokens);
+            number = true;
+        }
+        if let Some(commands) = commands(a) {
+            tokens.push(commands);
+            number = true;
+        }
+
+        let before = board.clone();
+        board.play(&mv);
+        while let Some(v) = variations.next_if(|v| v.start == i) {
+            let mut words = Vec::new();
+            push_line(before.clone(), v, &mut words)?;
+            // the parentheses can't go inside a `;` comment
+            match words.first_mut() {
+                Some(first) if !first.starts_with(';') => first.insert(0, '('),
+                _ => words.insert(0, "(".to_owned()),
+            }
+            match words.last_mut() {
+                Some(last) if !last.starts_with(';') => last.push(')'),
+                _ => words.push(")".to_owned()),
+            }
+            tokens.extend(words);
+            number = true;
+        }
+    }
+    Ok(())
+}
+
+/// join `tokens` with spaces, breaking lines before they grow longer than
+/// [LINE_WIDTH] and after `;` comments, which run to the end of the line
+fn wrap(tokens: &[String]) -> String {
+    let mut ret = String::new();
+    let mut len = 0;
+    for token in tokens {
+        let n = token.chars().count();
+        if len > 0 && len + 1 + n > LINE_WIDTH {
+            ret.push('\n');
+            len = 0;
+        } else if len > 0 {
+            ret.push(' ');
+            len += 1;
+        }
+        ret.push_str(token);
+        len += n;
+        if token.starts_with(';') {
+            ret.push('\n');
+            len = 0;
+        }
+    }
+    ret.push('\n');
+    ret
+}
+
+impl Game {
+    /// write the game as PGN in export format: the Seven Tag Roster in order,
+    /// filling in missing tags with `?`, then the rest of the tags sorted by
+    /// name, and the movetext with its comments, NAGs, and variations wrapped
+    /// to fit in 80 columns. the moves are written in standard SAN, so they
+    /// have to be legal
+    pub fn to_pgn(&self) -> Result<String, GameError> {
+        let mut ret = String::new();
+        for name in SEVEN_TAG_ROSTER {
+            let value = match (self.tags.get(name), name) {
+                (Some(raw), _) => unquote(raw),
+                (None, "Date") => "????.??.??".to_owned(),
+                (None, "Result") => self.result.clone(),
+                (None, _) => "?".to_owned(),
+            };
+            writeln!(ret, "[{name} {}]", quote(&value)).unwrap();
+        }
+        let mut rest: Vec<_> = self
+            .tags
+            .iter()
+            .filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str()))
+            .collect();
+        rest.sort();
+        for (name, raw) in rest {
+            writeln!(ret, "[{name} {}]", quote(&unquote(raw))).unwrap();
+        }
+        ret.push('\n');
+
+        let mut tokens = Vec::new();
+        push_line(self.starting_position()?, &self.main, &mut tokens)?;
+        tokens.push(self.result.clone());
+        ret.push_str(&wrap(&tokens));
+        Ok(ret)
+    }
+}
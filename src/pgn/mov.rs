@@ -12,11 +12,76 @@ pub enum Move {
 
         dest_rank: usize,
         dest_file: file::File,
+
+        /// the piece a pawn is promoted to when reaching the last rank
+        promotion: Option<PieceType>,
     },
     KingCastle,
     QueenCastle,
 }
 
+/// the ways a SAN move can fail to parse
+#[derive(Clone, Debug, PartialEq)]
+pub enum MoveParseError {
+    Empty,
+
+    /// the move started with an uppercase letter that isn't a piece
+    Piece(char),
+
+    /// a pawn promoted to something other than a queen, rook, bishop, or
+    /// knight
+    Promotion(char),
+
+    /// the move didn't have the shape of a SAN move
+    Syntax(String),
+}
+
+impl std::fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveParseError::Empty => write!(f, "empty move"),
+            MoveParseError::Piece(c) => write!(f, "unknown piece `{c}`"),
+            MoveParseError::Promotion(c) => {
+                write!(f, "invalid promotion piece `{c}`")
+            }
+            MoveParseError::Syntax(s) => write!(f, "invalid SAN move `{s}`"),
+        }
+    }
+}
+
+impl std::error::Error for MoveParseError {}
+
+/// the check (`+`) or checkmate (`#`) marker that may follow a SAN move
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Indicator {
+    Check,
+    Checkmate,
+}
+
+impl Indicator {
+    /// read the marker from the end of the SAN move `s`, accepting the older
+    /// `++` spelling for checkmate and ignoring suffix annotations like `!?`
+    pub fn from_san(s: &str) -> Option<Self> {
+        let s = s.trim_end_matches(['!', '?']);
+        if s.ends_with('#') || s.ends_with("++") {
+            Some(Self::Checkmate)
+        } else if s.ends_with('+') {
+            Some(Self::Check)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for Indicator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Indicator::Check => write!(f, "+"),
+            Indicator::Checkmate => write!(f, "#"),
+        }
+    }
+}
+
 mod display {
     use std::fmt::Display;
 
@@ -31,6 +96,7 @@ mod display {
                     from_file,
                     dest_rank,
                     dest_file,
+                    promotion,
                 } => {
                     let mut s = String::new();
                     let t = char::from(*typ);
@@ -59,6 +125,10 @@ mod display {
                     s.push(
                         char::from_digit(*dest_rank as u32 + 1, 10).unwrap(),
                     );
+                    if let Some(p) = promotion {
+                        s.push('=');
+                        s.push(char::from(*p));
+                    }
                     write!(f, "{s}")
                 }
                 Move::KingCastle => write!(f, "O-O"),
@@ -70,128 +140,138 @@ mod display {
 
 mod from_str {
     use crate::{
-        board::{
-            file::{self, File},
-            PieceType,
-        },
-        pgn::mov::Move,
+        board::{file::File, PieceType},
+        pgn::mov::{Move, MoveParseError},
     };
     use core::str::FromStr;
 
     impl FromStr for Move {
-        type Err = ();
+        type Err = MoveParseError;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            // skip check and mate indicators
-            let chars: Vec<_> =
-                s.chars().filter(|&x| !"+#".contains(x)).collect();
-            let typ = if !chars[0].is_ascii_uppercase() {
+            // skip suffix annotations and check and mate indicators, which
+            // are read separately by [crate::pgn::nag::Nag::from_suffix] and
+            // [super::Indicator::from_san]
+            let san =
+                s.trim_end_matches(['!', '?']).trim_end_matches(['+', '#']);
+            let chars: Vec<_> = san.chars().collect();
+            let Some(&first) = chars.first() else {
+                return Err(MoveParseError::Empty);
+            };
+            let typ = if !first.is_ascii_uppercase() {
                 PieceType::Pawn
             } else {
-                PieceType::from(chars[0])
+                PieceType::try_from(first)
+                    .map_err(|()| MoveParseError::Piece(first))?
             };
 
             match typ {
-                PieceType::King { .. } => {
-                    if s == "O-O" {
-                        Ok(Self::KingCastle)
-                    } else if s == "O-O-O" {
-                        Ok(Self::QueenCastle)
-                    } else {
-                        knight_move(chars, typ)
-                    }
-                }
-                PieceType::Queen => knight_move(chars, typ),
-                PieceType::Rook { .. } => knight_move(chars, typ),
-                PieceType::Bishop => bishop_move(chars, typ),
-                PieceType::Knight => knight_move(chars, typ),
-                PieceType::Pawn => pawn_move(chars, typ),
+                PieceType::King if san == "O-O" => Ok(Self::KingCastle),
+                PieceType::King if san == "O-O-O" => Ok(Self::QueenCastle),
+                PieceType::Pawn => pawn_move(s, chars),
+                _ => piece_move(s, &chars[1..], typ),
             }
         }
     }
 
-    fn bishop_move(chars: Vec<char>, typ: PieceType) -> Result<Move, ()> {
-        knight_move(chars, typ)
+    /// parse the rest of a move by a piece other than a pawn, after its
+    /// letter, like the `bd7` of `Nbd7`
+    fn piece_move(
+        s: &str,
+        chars: &[char],
+        typ: PieceType,
+    ) -> Result<Move, MoveParseError> {
+        let err = || MoveParseError::Syntax(s.to_owned());
+        // the capture marker doesn't change which move is meant
+        let chars: Vec<_> =
+            chars.iter().copied().filter(|&c| c != 'x').collect();
+        let (from, dest) =
+            chars.split_at(chars.len().checked_sub(2).ok_or_else(err)?);
+        let (dest_file, dest_rank) = square(dest).ok_or_else(err)?;
+        let (from_file, from_rank) = match *from {
+            [] => (None, None),
+            // disambiguating file or rank, eg Nbd7 or N2f4
+            [c] => match File::try_from(c) {
+                Ok(file) => (Some(file as usize), None),
+                Err(()) => (None, Some(rank(c).ok_or_else(err)?)),
+            },
+            // both, eg Ng6f4
+            [f, r] => {
+                let (f, r) = square(&[f, r]).ok_or_else(err)?;
+                (Some(f as usize), Some(r))
+            }
+            _ => return Err(err()),
+        };
+        Ok(Move::Normal {
+            typ,
+            from_rank,
+            from_file,
+            dest_rank,
+            dest_file,
+            promotion: None,
+        })
     }
 
-    fn knight_move(chars: Vec<char>, typ: PieceType) -> Result<Move, ()> {
-        if chars.len() == 3 {
-            let (dest_file, dest_rank) = pawn_dest(&chars[1..]);
-            return Ok(Move::Normal {
-                typ,
-                from_rank: None,
-                from_file: None,
-                dest_rank,
-                dest_file,
-            });
-        } else if chars.len() == 4 {
-            // disambiguating file, eg Nbd7
-            let from_file = if let Ok(file) = File::try_from(chars[1]) {
-                Some(file as usize)
-            } else {
-                // capture
-                None
-            };
-            // disambiguating rank, eg N2f4
-            let from_rank = char::to_digit(chars[1], 10).map(|r| r as usize);
-            let (dest_file, dest_rank) = pawn_dest(&chars[2..]);
-            return Ok(Move::Normal {
-                typ,
-                from_rank,
-                from_file,
-                dest_rank,
-                dest_file,
-            });
-        } else if chars.len() == 5 {
-            // both a rank and a capture, eg Ng6f4
-            let (f, r) = pawn_dest(&chars[1..]);
-            let (dest_file, dest_rank) = pawn_dest(&chars[3..]);
-            return Ok(Move::Normal {
-                typ,
-                from_rank: Some(r),
-                from_file: Some(f as usize),
-                dest_rank,
-                dest_file,
-            });
-        }
-        eprintln!("chars = {chars:?}");
-        Err(())
+    /// parse a square like `f4` into its file and 0-based rank
+    fn square(chars: &[char]) -> Option<(File, usize)> {
+        let [f, r] = *chars else {
+            return None;
+        };
+        Some((File::try_from(f).ok()?, rank(r)?))
     }
 
-    fn pawn_dest(chars: &[char]) -> (file::File, usize) {
-        (
-            file::File::from_unchecked(chars[0]),
-            char::to_digit(chars[1], 10).unwrap() as usize - 1,
-        )
+    /// parse a rank digit from 1 to 8 into a 0-based rank
+    fn rank(c: char) -> Option<usize> {
+        match c.to_digit(10)? {
+            r @ 1..=8 => Some(r as usize - 1),
+            _ => None,
+        }
     }
 
-    fn pawn_move(chars: Vec<char>, typ: PieceType) -> Result<Move, ()> {
-        if chars.len() == 2 {
-            let (dest_file, dest_rank) = pawn_dest(&chars);
-            return Ok(Move::Normal {
-                typ,
-                from_file: Some(dest_file as usize),
-                from_rank: None,
-                dest_file,
-                dest_rank,
-            });
-        } else if chars.len() == 4 {
-            // split 'exf4' into 'e' and 'f4'
-            let mut res = chars.split(|c| *c == 'x');
-            let x = res.next().unwrap()[0];
-            let from_file = Some(File::from_unchecked(x) as usize);
-            let y = res.next().unwrap();
-            let (dest_file, dest_rank) = pawn_dest(y);
-            return Ok(Move::Normal {
-                typ,
-                from_rank: None,
-                from_file,
-                dest_rank,
-                dest_file,
-            });
+    /// split a trailing promotion piece off of a pawn move, accepting the
+    /// common spellings `e8=Q`, `e8/Q`, `e8(Q)`, and `e8Q`
+    fn split_promotion(
+        mut chars: Vec<char>,
+    ) -> Result<(Vec<char>, Option<PieceType>), MoveParseError> {
+        let n = chars.len();
+        let (end, p) = match chars[..] {
+            [.., '(', p, ')'] => (n - 3, p.to_ascii_uppercase()),
+            [.., '=' | '/', p] => (n - 2, p.to_ascii_uppercase()),
+            [.., p] if p.is_ascii_uppercase() => (n - 1, p),
+            _ => return Ok((chars, None)),
+        };
+        if !"QRBN".contains(p) {
+            return Err(MoveParseError::Promotion(p));
         }
-        eprintln!("chars = {chars:?}");
-        Err(())
+        chars.truncate(end);
+        let p = PieceType::try_from(p)
+            .map_err(|()| MoveParseError::Promotion(p))?;
+        Ok((chars, Some(p)))
+    }
+
+    fn pawn_move(s: &str, chars: Vec<char>) -> Result<Move, MoveParseError> {
+        let err = || MoveParseError::Syntax(s.to_owned());
+        let (chars, promotion) = split_promotion(chars)?;
+        let (from_file, (dest_file, dest_rank)) = match chars[..] {
+            [f, r] => {
+                let dest = square(&[f, r]).ok_or_else(err)?;
+                (dest.0, dest)
+            }
+            // a capture like exf4
+            [x, 'x', f, r] => (
+                File::try_from(x).map_err(|()| err())?,
+                square(&[f, r]).ok_or_else(err)?,
+            ),
+            _ => return Err(err()),
+        };
+        Ok(Move::Normal {
+            typ: PieceType::Pawn,
+            from_rank: None,
+            from_file: Some(from_file as usize),
+            dest_rank,
+            dest_file,
+            promotion,
+        })
     }
 
     #[cfg(test)]
@@ -210,6 +290,7 @@ mod from_str {
                 from_file: Some(4),
                 dest_rank: 3,
                 dest_file: File::E,
+                promotion: None,
             };
             assert_eq!(got, want);
         }
@@ -223,6 +304,7 @@ mod from_str {
                 from_file: Some(2),
                 dest_rank: 4,
                 dest_file: File::C,
+                promotion: None,
             };
             assert_eq!(got, want);
         }
@@ -236,8 +318,77 @@ mod from_str {
                 from_file: Some(6),
                 dest_rank: 3,
                 dest_file: File::F,
+                promotion: None,
             };
             assert_eq!(got, want);
         }
+
+        #[test]
+        fn promotion() {
+            let want = Move::Normal {
+                typ: Pawn,
+                from_rank: None,
+                from_file: Some(4),
+                dest_rank: 7,
+                dest_file: File::E,
+                promotion: Some(Queen),
+            };
+            for s in ["e8=Q", "e8Q", "e8/Q", "e8(Q)", "e8=Q+", "e8=Q#"] {
+                assert_eq!(Move::from_str(s).unwrap(), want, "{s}");
+            }
+            assert!(Move::from_str("e8=K").is_err());
+        }
+
+        #[test]
+        fn capture_promotion() {
+            let want = Move::Normal {
+                typ: Pawn,
+                from_rank: None,
+                from_file: Some(4),
+                dest_rank: 0,
+                dest_file: File::D,
+                promotion: Some(Knight),
+            };
+            for s in ["exd1=N+", "exd1N", "exd1(N)"] {
+                assert_eq!(Move::from_str(s).unwrap(), want, "{s}");
+            }
+        }
+
+        #[test]
+        fn errors() {
+            use MoveParseError::*;
+            for (s, want) in [
+                ("", Empty),
+                ("Xe4", Piece('X')),
+                ("e8=K", Promotion('K')),
+                ("e9", Syntax("e9".to_owned())),
+                ("Nbxd9", Syntax("Nbxd9".to_owned())),
+                ("Nb1c2d3", Syntax("Nb1c2d3".to_owned())),
+                ("exf", Syntax("exf".to_owned())),
+                ("N", Syntax("N".to_owned())),
+            ] {
+                assert_eq!(Move::from_str(s), Err(want), "{s}");
+            }
+            // a capture with a disambiguating file used to crash
+            assert!(Move::from_str("Nbxd7").is_ok());
+        }
+
+        #[test]
+        fn indicators() {
+            use super::super::Indicator::{self, *};
+            assert_eq!(Move::from_str("O-O+").unwrap(), Move::KingCastle);
+            assert_eq!(Move::from_str("O-O-O#").unwrap(), Move::QueenCastle);
+            assert_eq!(Move::from_str("O-O!?").unwrap(), Move::KingCastle);
+            assert!(Move::from_str("Qxf7#??").is_ok());
+            for (s, want) in [
+                ("e4", None),
+                ("Qxf7#", Some(Checkmate)),
+                ("Qxf7++", Some(Checkmate)),
+                ("exd1=N+", Some(Check)),
+                ("exd1=N+!", Some(Check)),
+            ] {
+                assert_eq!(Indicator::from_san(s), want, "{s}");
+            }
+        }
     }
 }
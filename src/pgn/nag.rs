@@ -0,0 +1,45 @@
+use std::{fmt::Display, str::FromStr};
+
+/// the traditional suffix annotations, which stand for the first six NAGs
+const GLYPHS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+/// a Numeric Annotation Glyph, like `$4` for a very poor move, from 0 to 255
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Nag(pub u8);
+
+impl Nag {
+    /// the suffix annotation like `!?` that this NAG stands for, if there is
+    /// one
+    pub fn glyph(&self) -> Option<&'static str> {
+        GLYPHS.get((self.0 as usize).checked_sub(1)?).copied()
+    }
+
+    /// split a suffix annotation like the `??` of `Qxf7??` off of the move
+    /// `san`
+    pub fn from_suffix(san: &str) -> (&str, Option<Self>) {
+        let move_end = san.trim_end_matches(['!', '?']).len();
+        let (san, suffix) = san.split_at(move_end);
+        (san, suffix.parse().ok())
+    }
+}
+
+impl FromStr for Nag {
+    type Err = ();
+
+    /// parse either a NAG like `$4` or a suffix annotation like `??`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(n) = s.strip_prefix('$') {
+            return n.parse().map(Self).map_err(|_| ());
+        }
+        let i = GLYPHS.iter().position(|&g| g == s).ok_or(())?;
+        Ok(Self(i as u8 + 1))
+    }
+}
+
+/// the export format for NAGs, like `$4`. see [Nag::glyph] for the shorter
+/// form readers are used to
+impl Display for Nag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
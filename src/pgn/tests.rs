@@ -1,8 +1,23 @@
 use super::*;
 use crate::board::file::File::*;
+use crate::board::IllegalMove;
 use crate::board::PieceType::*;
+use crate::pgn::mov::Indicator::*;
 use crate::pgn::mov::Move::KingCastle;
 use crate::pgn::mov::Move::Normal;
+use crate::pgn::mov::MoveParseError;
+use crate::pgn::validate::Mismatch;
+
+/// every game in the PGN files in `testfiles`, along with the name of its
+/// file
+pub(crate) fn fixture_games() -> Vec<(&'static str, Game)> {
+    let mut ret = Vec::new();
+    for f in ["sample", "multi", "enpassant", "setup", "chess960"] {
+        let pgn = Pgn::load(format!("testfiles/{f}.pgn")).unwrap();
+        ret.extend(pgn.games.into_iter().map(|game| (f, game)));
+    }
+    ret
+}
 
 #[test]
 fn load_single() {
@@ -18,3 +33,351 @@ fn load_multi() {
     let want = include!("../../testfiles/multi.want");
     assert_eq!(got, want);
 }
+
+#[test]
+fn setup_fen() {
+    let game = &Pgn::load("testfiles/setup.pgn").unwrap().games[0];
+    let mut board = game.starting_position().unwrap();
+    assert_eq!(board.fen(), "8/8/8/4k3/8/8/4P3/4K3 b - - 0 40");
+    for m in &game.main.moves {
+        board.make_move(m).unwrap();
+    }
+    let got = board.fen();
+    assert_eq!(got, "8/8/8/8/4k3/4P3/3K4/8 b - - 0 42");
+}
+
+#[test]
+fn chess960() {
+    let game = &Pgn::load("testfiles/chess960.pgn").unwrap().games[0];
+    assert!(game.is_chess960());
+    let mut board = game.starting_position().unwrap();
+    assert!(board.chess960());
+    let mut moves = Vec::new();
+    for m in &game.main.moves {
+        moves.push(board.uci(m).unwrap());
+        board.make_move(m).unwrap();
+    }
+    assert_eq!(moves[8..], ["d1h1", "d8h8"]);
+    assert_eq!(
+        board.fen(),
+        "rbb2rk1/ppppppqp/5nn1/6p1/6P1/5NN1/PPPPPPQP/RBB2RK1 w - - 8 6"
+    );
+}
+
+#[test]
+fn read_errors() {
+    let input = b"[Event \"a\"]
+
+1. e4 e5 2. Nf3 Nc6 *
+
+[Event \"b\"]
+
+1. e4 e5
+2. Kx9 *
+
+[]
+
+1. d4 *
+";
+    let err = Pgn::read(&input[..]).unwrap_err();
+    assert_eq!((err.line, err.col), (8, 4));
+    assert!(matches!(
+        err.kind,
+        PgnErrorKind::Move(MoveParseError::Syntax(ref s)) if s == "Kx9"
+    ));
+    assert_eq!(err.to_string(), "line 8, column 4: invalid SAN move `Kx9`");
+
+    let (pgn, errors) = Pgn::read_lenient(&input[..]).unwrap();
+    assert_eq!(pgn.games.len(), 1);
+    assert_eq!(pgn.games[0].tag("Event"), Some("a"));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, 1);
+    assert_eq!((errors[1].0, errors[1].1.line), (2, 10));
+    assert!(matches!(errors[1].1.kind, PgnErrorKind::Tag(_)));
+
+    let err = Pgn::read(&b"[Event \"a\"]\n\n"[..]).unwrap_err();
+    assert!(matches!(err.kind, PgnErrorKind::MissingResult));
+
+    let err = Pgn::read(&b"1. e4 e5"[..]).unwrap_err();
+    assert!(matches!(err.kind, PgnErrorKind::MissingResult));
+}
+
+#[test]
+fn move_numbers() {
+    let got = Pgn::read(&b"1.e4 e5 2.Nf3 2...Nc6 3.Bb5 *"[..]).unwrap();
+    let want = Pgn::read(&b"1. e4 e5 2. Nf3 Nc6 3. Bb5 *"[..]).unwrap();
+    assert_eq!(got.games[0].main.moves.len(), 5);
+    assert_eq!(got, want);
+
+    // errors point at the move, not at its number
+    let err = Pgn::read(&b"1. e4 e5 2.Kx9 *"[..]).unwrap_err();
+    assert_eq!((err.line, err.col), (1, 12));
+}
+
+#[test]
+fn replay_errors() {
+    let pgn = Pgn::read(&b"1. e4 e5 2. Ke3 *"[..]).unwrap();
+    let err = pgn.games[0].replay().unwrap_err();
+    assert!(matches!(
+        err,
+        GameError::Illegal(IllegalMove { ply: 3, .. })
+    ));
+
+    // the move is reported as it was written
+    let pgn = Pgn::read(&b"1. e4 e5 2. exf7+ *"[..]).unwrap();
+    let err = pgn.games[0].replay().unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("illegal move 2. exf7+ in position"),
+        "{err}"
+    );
+
+    let pgn =
+        Pgn::read(&b"[FEN \"8/8/8/8 w - - 0 1\"]\n\n1. e4 *"[..]).unwrap();
+    let err = pgn.games[0].replay().unwrap_err();
+    assert!(matches!(err, GameError::Fen(_)));
+}
+
+#[test]
+fn variations() {
+    let pgn = Pgn::read(
+        &b"1. e4 (1. d4 d5 (1... Nf6 2. c4) 2. c4) (1. c4) 1... e5 (1... c5
+(1... e6)) 2. Nf3 {a comment (not a variation)} Nc6 *"[..],
+    )
+    .unwrap();
+    let game = &pgn.games[0];
+    assert_eq!(game.main.moves.len(), 4);
+    let starts: Vec<_> = game.main.variations.iter().map(|v| v.start).collect();
+    assert_eq!(starts, [0, 0, 1]);
+    assert_eq!(game.main.variations[0].to_string(), "d4 d5 (Nf6 c4) c4");
+    assert_eq!(game.main.variations[0].variations[0].start, 1);
+    assert_eq!(game.main.variations[2].to_string(), "c5 (e6)");
+    game.replay().unwrap();
+
+    // an illegal move in a variation is still an illegal move
+    let pgn = Pgn::read(&b"1. e4 (1. Ke2) e5 *"[..]).unwrap();
+    let err = pgn.games[0].replay().unwrap_err();
+    assert!(matches!(
+        err,
+        GameError::Illegal(IllegalMove { ply: 1, .. })
+    ));
+
+    for (input, (line, col), want) in [
+        (&b"1. e4 (1. d4 e5 *"[..], (1, 7), "unmatched `(`"),
+        (b"1. e4 e5) *", (1, 9), "unmatched `)`"),
+        (
+            b"(1. d4) 1. e4 *",
+            (1, 1),
+            "variation without a move to replace",
+        ),
+    ] {
+        let err = Pgn::read(input).unwrap_err();
+        assert_eq!((err.line, err.col), (line, col));
+        assert_eq!(
+            err.to_string(),
+            format!("line {line}, column {col}: {want}")
+        );
+    }
+}
+
+#[test]
+fn comments() {
+    let pgn = Pgn::read(
+        &b"{Notes by a coach} 1. e4 {the most popular
+move} {[%clk 0:03:00]} (1. d4 {also good}) {back to e4} 1... e5 ; a reply
+2. Nf3 Nc6 (; a side line
+{starting with} 2... d6 {Philidor}) 3. Bb5 {the Spanish} *"[..],
+    )
+    .unwrap();
+    let game = &pgn.games[0];
+    let a = &game.main.annotations;
+    assert_eq!(a[0].before, ["Notes by a coach"]);
+    assert_eq!(a[0].after, ["the most popular move"]);
+    assert_eq!(a[0].clock, Some(Duration::from_secs(180)));
+    assert_eq!(a[1].before, ["back to e4"]);
+    assert_eq!(a[1].after, ["a reply"]);
+    assert_eq!(a[4].after, ["the Spanish"]);
+    assert_eq!(game.main.variations[0].to_string(), "d4 {also good}");
+    assert_eq!(
+        game.main.variations[1].to_string(),
+        "{a side line} {starting with} d6 {Philidor}"
+    );
+
+    // a line of a comment starting with `[` isn't a tag pair
+    for input in [
+        &b"1. e4 {first line\n[%clk 0:00:59]} e5 *"[..],
+        b"[Event \"a\"]\n\n1. e4 {first line\n[%clk 0:00:59]} e5 *",
+    ] {
+        let game = &Pgn::read(input).unwrap().games[0];
+        assert_eq!(game.main.moves.len(), 2);
+        let a = &game.main.annotations[0];
+        assert_eq!(a.after, ["first line"]);
+        assert_eq!(a.clock, Some(Duration::from_secs(59)));
+        assert!(!game.tags.contains_key("%clk"));
+    }
+
+    let err = Pgn::read(&b"1. e4 {unfinished\n*"[..]).unwrap_err();
+    assert_eq!(err.to_string(), "line 1, column 7: unclosed comment");
+}
+
+#[test]
+fn nags() {
+    let pgn = Pgn::read(
+        &b"1. e4!? e5 $2 $32 2. Qh5?! Nc6 3. Bc4 Nf6?? 4. Qxf7#! ! 1-0"[..],
+    )
+    .unwrap();
+    let game = &pgn.games[0];
+    let nags: Vec<_> = game.main.annotations.iter().map(|a| &a.nags).collect();
+    use crate::pgn::nag::Nag;
+    assert_eq!(
+        nags,
+        [
+            &vec![Nag(5)],
+            &vec![Nag(2), Nag(32)],
+            &vec![Nag(6)],
+            &vec![],
+            &vec![],
+            &vec![Nag(4)],
+            &vec![Nag(1), Nag(1)],
+        ]
+    );
+    assert_eq!(game.main.checks[6], Some(Checkmate));
+    assert_eq!(
+        game.main.to_string(),
+        "e4!? e5? $32 Qh5?! Nc6 Bc4 Nf6?? Qf7#! $1"
+    );
+
+    for (input, want) in [
+        (&b"1. e4 $x *"[..], "line 1, column 7: invalid NAG `$x`"),
+        (b"1. e4 ?!? *", "line 1, column 7: invalid NAG `?!?`"),
+        (
+            b"$1 1. e4 *",
+            "line 1, column 1: NAG without a move to annotate",
+        ),
+    ] {
+        let err = Pgn::read(input).unwrap_err();
+        assert_eq!(err.to_string(), want);
+    }
+}
+
+#[test]
+fn export() {
+    let input = br#"[White "Morphy, \"Paul\""]
+[Black "?"]
+[Event "Paris"]
+[Opening "Philidor Defense"]
+[ECO "C41"]
+
+{Notes by a coach} 1. e4 e5 2. Nf3 d6 {[%clk 0:04:57] the Philidor} 3. d4 Bg4?!
+(3... exd4 {is more common} 4. Nxd4 (4. Qxd4) $10) 4. dxe5 Bxf3 5. Qxf3 dxe5 6.
+Bc4 Nf6 7. Qb3 Qe7 8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O
+Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+    let pgn = Pgn::read(&input[..]).unwrap();
+    let got = pgn.games[0].to_pgn().unwrap();
+    let want = r#"[Event "Paris"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "Morphy, \"Paul\""]
+[Black "?"]
+[Result "1-0"]
+[ECO "C41"]
+[Opening "Philidor Defense"]
+
+{Notes by a coach} 1. e4 e5 2. Nf3 d6 {the Philidor} {[%clk 0:04:57]} 3. d4 Bg4
+$6 (3... exd4 {is more common} 4. Nxd4 $10 (4. Qxd4)) 4. dxe5 Bxf3 5. Qxf3 dxe5
+6. Bc4 Nf6 7. Qb3 Qe7 8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12.
+O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+    assert_eq!(got, want);
+
+    // a `}` can't go in a brace comment, so it stays in a `;` comment
+    let game = &Pgn::read(&b"1. e4 ; see {this} here\ne5 *"[..])
+        .unwrap()
+        .games[0];
+    let pgn = game.to_pgn().unwrap();
+    assert!(
+        pgn.ends_with("\n1. e4 ; see {this} here\n1... e5 *\n"),
+        "{pgn}"
+    );
+    let got = &Pgn::read(pgn.as_bytes()).unwrap().games[0];
+    assert_eq!(got.main, game.main);
+    assert_eq!(got.main.annotations[0].after, ["see {this} here"]);
+
+    // nor can a `)`, and a line can't start with a `[` or `%` from inside a
+    // comment, wherever it falls
+    let mut inputs = vec!["1. e4 (1. d4 ; a } brace\n) 1... e5 *".to_owned()];
+    for n in 0..30 {
+        let words = "ab ".repeat(n);
+        inputs.push(format!("1. e4 {{{words}[%cal Ge2e4] done %x}} *"));
+    }
+    for input in inputs {
+        let game = &Pgn::read(input.as_bytes()).unwrap().games[0];
+        let pgn = game.to_pgn().unwrap();
+        let got = &Pgn::read(pgn.as_bytes()).unwrap().games[0];
+        assert_eq!(got.main, game.main, "{pgn}");
+    }
+
+    // everything survives writing the games out and reading them back in
+    for (f, game) in fixture_games() {
+        let pgn = game.to_pgn().unwrap();
+        assert!(pgn.lines().all(|line| line.len() < 80), "{f}");
+        let mut got = Pgn::read(pgn.as_bytes()).unwrap().games.remove(0);
+        // the only tags added are the missing ones from the Seven Tag Roster
+        got.tags.retain(|name, _| {
+            let added = !game.tags.contains_key(name);
+            assert!(
+                !added || export::SEVEN_TAG_ROSTER.contains(&name.as_str()),
+                "{f}: {name}"
+            );
+            !added
+        });
+        assert_eq!(got, game, "{f}");
+    }
+}
+
+#[test]
+fn validate_testfiles() {
+    for (f, game) in fixture_games() {
+        assert_eq!(game.validate().unwrap(), vec![], "{f}");
+    }
+}
+
+#[test]
+fn validate_mismatches() {
+    let pgn = Pgn::read(
+        &b"[Result \"1-0\"]
+
+1. e4 e5 2. Qh5+ Nc6 3. Bc4 Nf6 4. Qxf7 0-1"[..],
+    )
+    .unwrap();
+    let got: Vec<_> = pgn.games[0].validate().unwrap();
+    let want = vec![
+        Mismatch::Indicator {
+            number: "2.".to_owned(),
+            san: "Qh5".to_owned(),
+            marked: Some(Check),
+            actual: None,
+        },
+        Mismatch::Indicator {
+            number: "4.".to_owned(),
+            san: "Qxf7".to_owned(),
+            marked: None,
+            actual: Some(Checkmate),
+        },
+        Mismatch::Result {
+            result: "0-1".to_owned(),
+            want: "1-0",
+        },
+        Mismatch::ResultTag {
+            tag: "1-0".to_owned(),
+            movetext: "0-1".to_owned(),
+        },
+    ];
+    assert_eq!(got, want);
+    assert_eq!(
+        got[1].to_string(),
+        "4. Qxf7 is not marked but gives checkmate"
+    );
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use super::{commands::Eval, mov::Move, Game, GameError, Variation};
+use crate::board::Board;
+
+impl Move {
+    /// the corresponding move on the [Board::flipped] board
+    pub fn flipped(&self) -> Self {
+        match self.clone() {
+            Move::Normal {
+                typ,
+                from_rank,
+                from_file,
+                dest_rank,
+                dest_file,
+                promotion,
+            } => Move::Normal {
+                typ,
+                from_rank: from_rank.map(|r| 7 - r),
+                from_file,
+                dest_rank: 7 - dest_rank,
+                dest_file,
+                promotion,
+            },
+            castle => castle,
+        }
+    }
+
+    /// the corresponding move on the [Board::mirrored] board, where castling
+    /// kingside turns into castling queenside and the other way around
+    pub fn mirrored(&self) -> Self {
+        match self.clone() {
+            Move::Normal {
+                typ,
+                from_rank,
+                from_file,
+                dest_rank,
+                dest_file,
+                promotion,
+            } => Move::Normal {
+                typ,
+                from_rank,
+                from_file: from_file.map(|f| 7 - f),
+                dest_rank,
+                dest_file: dest_file.mirrored(),
+                promotion,
+            },
+            Move::KingCastle => Move::QueenCastle,
+            Move::QueenCastle => Move::KingCastle,
+        }
+    }
+}
+
+impl Variation {
+    /// report whether anyone castles in `self` or the variations nested
+    /// inside it
+    fn castles(&self) -> bool {
+        self.moves
+            .iter()
+            .any(|m| matches!(m, Move::KingCastle | Move::QueenCastle))
+            || self.variations.iter().any(Variation::castles)
+    }
+
+    /// the same moves and the ones in the variations nested inside them,
+    /// either [Move::flipped], with their evaluations negated, or
+    /// [Move::mirrored]
+    fn transformed(&self, flip: bool) -> Self {
+        let mut annotations = self.annotations.clone();
+        if flip {
+            for a in &mut annotations {
+                a.eval = a.eval.map(|e| match e {
+                    Eval::Pawns(p) => Eval::Pawns(-p),
+                    Eval::Mate(n) => Eval::Mate(-n),
+                });
+            }
+        }
+        let f: fn(&Move) -> Move =
+            if flip { Move::flipped } else { Move::mirrored };
+        Self {
+            start: self.start,
+            moves: self.moves.iter().map(f).collect(),
+            sans: self
+                .sans
+                .iter()
+                .map(|san| transform_san(san, flip))
+                .collect(),
+            checks: self.checks.clone(),
+            annotations,
+            variations: self
+                .variations
+                .iter()
+                .map(|v| v.transformed(flip))
+                .collect(),
+        }
+    }
+}
+
+/// the SAN move `san` as written on the [Board::flipped] board if `flip` is
+/// true, and on the [Board::mirrored] board otherwise
+fn transform_san(san: &str, flip: bool) -> String {
+    if !flip {
+        // castling doesn't name any squares
+        if let Some(rest) = san.strip_prefix("O-O-O") {
+            return format!("O-O{rest}");
+        } else if let Some(rest) = san.strip_prefix("O-O") {
+            return format!("O-O-O{rest}");
+        }
+    }
+    san.chars()
+        .map(|c| match c {
+            '1'..='8' if flip => char::from(b'1' + b'8' - c as u8),
+            'a'..='h' if !flip => char::from(b'a' + b'h' - c as u8),
+            c => c,
+        })
+        .collect()
+}
+
+impl Game {
+    /// the same game played from the [Board::flipped] starting position, with
+    /// the colors of the players, the result, and the evaluations swapped
+    /// along with the colors of the pieces
+    pub fn flipped(&self) -> Result<Self, GameError> {
+        let start = self.starting_position()?.flipped();
+        let result = match self.result.as_str() {
+            "1-0" => "0-1".to_owned(),
+            "0-1" => "1-0".to_owned(),
+            r => r.to_owned(),
+        };
+        let mut tags: HashMap<_, _> = self
+            .tags
+            .iter()
+            .map(|(name, value)| {
+                let name = if let Some(rest) = name.strip_prefix("White") {
+                    format!("Black{rest}")
+                } else if let Some(rest) = name.strip_prefix("Black") {
+                    format!("White{rest}")
+                } else {
+                    name.clone()
+                };
+                (name, value.clone())
+            })
+            .collect();
+        if tags.contains_key("Result") {
+            tags.insert("Result".to_owned(), format!("\"{result}\""));
+        }
+        let mut ret = Self {
+            main: self.main.transformed(true),
+            result,
+            tags,
+        };
+        ret.set_start(&start);
+        Ok(ret)
+    }
+
+    /// the same game played from the [Board::mirrored] starting position,
+    /// which is a Chess960 game if anyone can still castle. the castled king
+    /// and rook wouldn't end up on the mirrored squares, so games where
+    /// anyone castles can't be mirrored
+    pub fn mirrored(&self) -> Result<Self, GameError> {
+        if self.main.castles() {
+            return Err(GameError::Castling);
+        }
+        let start = self.starting_position()?.mirrored();
+        let mut ret = Self {
+            main: self.main.transformed(false),
+            result: self.result.clone(),
+            tags: self.tags.clone(),
+        };
+        ret.set_start(&start);
+        if start.chess960() && !ret.is_chess960() {
+            ret.tags
+                .insert("Variant".to_owned(), "\"Chess960\"".to_owned());
+        }
+        Ok(ret)
+    }
+
+    /// point the `SetUp` and `FEN` tags at `board`
+    fn set_start(&mut self, board: &Board) {
+        self.tags.insert("SetUp".to_owned(), "\"1\"".to_owned());
+        self.tags
+            .insert("FEN".to_owned(), format!("\"{}\"", board.fen()));
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+use super::{mov::Indicator, Game, GameError};
+use crate::board::Color;
+
+/// a disagreement between the annotations in a [Game] and the position on the
+/// board when the game is replayed
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    /// the move `san`, numbered like `12.` or `12...` and written here in
+    /// SAN without its marker, was written with the marker `marked` but
+    /// actually gives `actual`
+    Indicator {
+        number: String,
+        san: String,
+        marked: Option<Indicator>,
+        actual: Option<Indicator>,
+    },
+
+    /// the result at the end of the movetext is impossible in the final
+    /// position, which was checkmate or stalemate and so requires `want`
+    Result { result: String, want: &'static str },
+
+    /// the `Result` tag disagrees with the result at the end of the movetext
+    ResultTag { tag: String, movetext: String },
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::Indicator {
+                number,
+                san,
+                marked,
+                actual,
+            } => {
+                write!(f, "{number} {san} is ")?;
+                match marked {
+                    Some(m) => write!(f, "marked with `{m}`")?,
+                    None => write!(f, "not marked")?,
+                }
+                match actual {
+                    Some(Indicator::Check) => write!(f, " but gives check"),
+                    Some(Indicator::Checkmate) => {
+                        write!(f, " but gives checkmate")
+                    }
+                    None => write!(f, " but gives no check"),
+                }
+            }
+            Mismatch::Result { result, want } => write!(
+                f,
+                "result {result} does not match the final position, \
+                 which requires {want}"
+            ),
+            Mismatch::ResultTag { tag, movetext } => write!(
+                f,
+                "Result tag {tag} does not match the movetext result \
+                 {movetext}"
+            ),
+        }
+    }
+}
+
+impl Game {
+    /// replay the game, checking that each move's check marker and the result
+    /// agree with the positions actually reached
+    pub fn validate(&self) -> Result<Vec<Mismatch>, GameError> {
+        let mut ret = Vec::new();
+        let mut board = self.starting_position()?;
+        for (i, &marked) in self.main.checks.iter().enumerate() {
+            let number = match board.to_move() {
+                Color::White => format!("{}.", board.full_move()),
+                Color::Black => format!("{}...", board.full_move()),
+            };
+            let mv = self.main.resolve(&board, i)?;
+            let san = board.san(&mv);
+            board.play(&mv);
+            let actual = if board.is_checkmate() {
+                Some(Indicator::Checkmate)
+            } else if board.is_check() {
+                Some(Indicator::Check)
+            } else {
+                None
+            };
+            if marked != actual {
+                ret.push(Mismatch::Indicator {
+                    number,
+                    san: san.trim_end_matches(['+', '#']).to_owned(),
+                    marked,
+                    actual,
+                });
+            }
+        }
+
+        let want = if board.is_checkmate() {
+            match board.to_move() {
+                Color::White => Some("0-1"),
+                Color::Black => Some("1-0"),
+            }
+        } else if board.is_stalemate() {
+            Some("1/2-1/2")
+        } else {
+            None
+        };
+        if let Some(want) = want
+            && self.result != want
+        {
+            ret.push(Mismatch::Result {
+                result: self.result.clone(),
+                want,
+            });
+        }
+
+        if let Some(tag) = self.tag("Result")
+            && tag != self.result
+        {
+            ret.push(Mismatch::ResultTag {
+                tag: tag.to_owned(),
+                movetext: self.result.clone(),
+            });
+        }
+
+        Ok(ret)
+    }
+}
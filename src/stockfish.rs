@@ -8,6 +8,60 @@ use std::process::{Child, ChildStdin};
 
 use crate::board::Color;
 
+/// the result of searching a position with [Stockfish::analyze]
+#[derive(Debug, PartialEq)]
+pub(crate) struct Analysis {
+    /// the score in pawns from White's point of view, or 99 for a forced mate
+    pub(crate) score: f64,
+
+    /// the move the engine would play, in UCI notation, if it has one
+    pub(crate) best_move: Option<String>,
+
+    /// the principal variation from the deepest search, in UCI notation
+    pub(crate) pv: Vec<String>,
+}
+
+impl Analysis {
+    /// parse the engine's `output` from a search for the side `to_move`
+    fn parse(output: &str, to_move: Color) -> Self {
+        let mut score = 0.0;
+        let mut pv = Vec::new();
+        let mut best_move = None;
+        for line in output.lines() {
+            if line.starts_with("info") {
+                let sp: Vec<_> = line.split_ascii_whitespace().collect();
+                if sp.contains(&"mate") {
+                    score = 99.0;
+                // not found on line saying NNUE is enabled
+                } else if let Some(i) = sp.iter().position(|&s| s == "cp") {
+                    score = sp[i + 1].parse::<f64>().unwrap();
+                    // stockfish reports the score as an integer in units of
+                    // centipawns for the side to move
+                    if to_move.is_black() {
+                        score *= -1.0;
+                    }
+                    score /= 100.0;
+                }
+                if let Some(i) = sp.iter().position(|&s| s == "pv") {
+                    pv = sp[i + 1..].iter().map(|&s| s.to_owned()).collect();
+                }
+            } else if let Some(rest) = line.strip_prefix("bestmove") {
+                // the engine sends `(none)` when there are no legal moves
+                best_move = rest
+                    .split_ascii_whitespace()
+                    .next()
+                    .filter(|&s| s != "(none)")
+                    .map(str::to_owned);
+            }
+        }
+        Self {
+            score,
+            best_move,
+            pv,
+        }
+    }
+}
+
 pub(crate) struct Stockfish {
     child: Child,
     pub(crate) stdin: ChildStdin,
@@ -62,6 +116,14 @@ impl Stockfish {
         s
     }
 
+    /// set the UCI option `name` to `value`, like `UCI_Chess960`
+    pub(crate) fn set_option<D>(&mut self, name: &str, value: D)
+    where
+        D: Display,
+    {
+        self.send(format!("setoption name {name} value {value}"));
+    }
+
     /// send `ucinewgame` followd by `isready` to prepare the engine for a
     /// position from a new game
     pub(crate) fn new_game(&mut self) {
@@ -74,40 +136,47 @@ impl Stockfish {
         self.receive("readyok");
     }
 
-    pub(crate) fn start_position(&mut self) {
-        self.send("position startpos");
-        self.is_ready();
-    }
-
-    /// set stockfish's position to `fen`
-    pub(crate) fn set_position(&mut self, fen: &impl Display) {
-        self.send(format!("position fen {fen}"));
+    /// set stockfish's position to `moves`, given in UCI notation, played from
+    /// `start`, which is either `startpos` or `fen` followed by a FEN
+    pub(crate) fn set_position(&mut self, start: &str, moves: &[String]) {
+        if moves.is_empty() {
+            self.send(format!("position {start}"));
+        } else {
+            self.send(format!("position {start} moves {}", moves.join(" ")));
+        }
     }
 
-    /// score the current position to `depth` for the the player `to_move`
-    pub(crate) fn get_score(&mut self, depth: usize, to_move: Color) -> f64 {
+    /// search the current position to `depth` for the player `to_move`
+    pub(crate) fn analyze(&mut self, depth: usize, to_move: Color) -> Analysis {
         self.send(format!("go depth {}", depth));
         let output = self.receive("bestmove");
-        let mut score = 0.0;
-        for line in output.split('\n') {
-            if line.starts_with("info") {
-                let mut sp = line.split_ascii_whitespace();
-                if sp.clone().any(|s| s == "mate") {
-                    return 99.0;
-                }
-                // not found on line saying NNUE is enabled
-                if sp.any(|s| s == "cp") {
-                    let text = sp.next().unwrap();
-                    score = text.parse::<f64>().unwrap();
-                }
-            }
-        }
+        Analysis::parse(&output, to_move)
+    }
+}
 
-        if to_move.is_black() {
-            score *= -1.0;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_analysis() {
+        let output = "\
+info string NNUE evaluation using nn-5af11540bbfe.nnue enabled
+info depth 1 seldepth 1 multipv 1 score cp 18 nodes 20 nps 10000 pv e7e5
+info depth 2 seldepth 2 multipv 1 score cp 32 nodes 62 nps 31000 pv e7e5 g1f3
+bestmove e7e5 ponder g1f3
+";
+        let got = Analysis::parse(output, Color::Black);
+        let want = Analysis {
+            score: -0.32,
+            best_move: Some("e7e5".to_owned()),
+            pv: vec!["e7e5".to_owned(), "g1f3".to_owned()],
+        };
+        assert_eq!(got, want);
 
-        // stockfish reports the score as an integer in units of centipawns
-        score / 100.0
+        let output = "info depth 0 score mate 0\nbestmove (none)\n";
+        let got = Analysis::parse(output, Color::White);
+        assert_eq!(got.score, 99.0);
+        assert_eq!(got.best_move, None);
     }
 }